@@ -0,0 +1,59 @@
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium_graphics::{
+    AdvancedBlend, BlendEquation, Flip, Glium2d, GliumWindow, OpenGL, Texture, TextureSettings,
+};
+use piston::input::RenderEvent;
+use piston::window::WindowSettings;
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (400, 200);
+    let ref mut window: GliumWindow = WindowSettings::new("glium_graphics: blend_modes", [w, h])
+        .exit_on_esc(true)
+        .graphics_api(opengl)
+        .build()
+        .unwrap();
+
+    let rust_logo = Texture::from_path(
+        window,
+        "assets/rust.png",
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+    let mut g2d = Glium2d::new(opengl, window);
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(args) = e.render_args() {
+            let mut target = window.draw();
+            g2d.draw(&mut target, args.viewport(), |c, g| {
+                clear([0.2, 0.3, 0.6, 1.0], g);
+                rectangle(
+                    [0.9, 0.6, 0.2, 1.0],
+                    [0.0, 0.0, 400.0, 100.0],
+                    c.transform,
+                    g,
+                );
+
+                // Fixed-function blending.
+                g.with_blend(BlendEquation::Screen, |g| {
+                    image(&rust_logo, c.transform.trans(20.0, 20.0).zoom(0.6), g);
+                });
+                g.with_blend(BlendEquation::Subtract, |g| {
+                    image(&rust_logo, c.transform.trans(150.0, 20.0).zoom(0.6), g);
+                });
+
+                // Blending in a shader.
+                g.with_advanced_blend(AdvancedBlend::Overlay, |g| {
+                    image(&rust_logo, c.transform.trans(280.0, 20.0).zoom(0.6), g);
+                })
+                .unwrap();
+            });
+            target.finish().unwrap();
+        }
+    }
+}
@@ -0,0 +1,30 @@
+extern crate glium_graphics;
+extern crate graphics;
+
+use glium_graphics::{Glium2d, Headless};
+
+// Renders without a window and saves the result, for example to compare with a golden image.
+fn main() {
+    let ref mut context = Headless::egl().unwrap();
+    let mut g2d = Glium2d::detect(context).unwrap();
+    let image = g2d
+        .draw_to_image(context, 200, 200, |c, g| {
+            use graphics::*;
+
+            clear(color::WHITE, g);
+            rectangle(
+                [1.0, 0.0, 0.0, 1.0],
+                [20.0, 20.0, 100.0, 100.0],
+                c.transform,
+                g,
+            );
+            ellipse(
+                [0.0, 0.0, 1.0, 0.5],
+                [60.0, 60.0, 120.0, 120.0],
+                c.transform,
+                g,
+            );
+        })
+        .unwrap();
+    image.save("headless.png").unwrap();
+}
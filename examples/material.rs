@@ -0,0 +1,82 @@
+extern crate glium;
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium::Program;
+use glium_graphics::{
+    Flip, Glium2d, GliumWindow, Material, MaterialUniform, OpenGL, Texture, TextureSettings,
+};
+use piston::input::{RenderEvent, UpdateEvent};
+use piston::window::WindowSettings;
+
+const VERTEX: &str = "
+#version 150 core
+in vec2 pos;
+in vec2 uv;
+out vec2 v_UV;
+
+void main() {
+    v_UV = uv;
+    gl_Position = vec4(pos, 0.0, 1.0);
+}
+";
+
+// Hides texels below a threshold, which dissolves the image over time.
+const FRAGMENT: &str = "
+#version 150 core
+uniform sampler2D s_texture;
+uniform vec4 color;
+uniform float threshold;
+in vec2 v_UV;
+out vec4 o_Color;
+
+void main() {
+    vec4 texel = texture(s_texture, v_UV);
+    float noise = fract(sin(dot(floor(v_UV * 32.0), vec2(12.9898, 78.233))) * 43758.5453);
+    if (noise < threshold) discard;
+    o_Color = texel * color;
+}
+";
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (300, 300);
+    let ref mut window: GliumWindow = WindowSettings::new("glium_graphics: material", [w, h])
+        .exit_on_esc(true)
+        .graphics_api(opengl)
+        .build()
+        .unwrap();
+
+    let rust_logo = Texture::from_path(
+        window,
+        "assets/rust.png",
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+    let program = Program::from_source(window, VERTEX, FRAGMENT, None).unwrap();
+    let mut dissolve = Material::new(program);
+    let mut time = 0.0;
+
+    let mut g2d = Glium2d::new(opengl, window);
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(args) = e.update_args() {
+            time += args.dt;
+        }
+        if let Some(args) = e.render_args() {
+            dissolve.set("threshold", MaterialUniform::Float(time.sin().abs() as f32));
+            let mut target = window.draw();
+            g2d.draw(&mut target, args.viewport(), |c, g| {
+                clear(color::WHITE, g);
+                image(&rust_logo, c.transform, g);
+                g.with_material(&dissolve, |g| {
+                    image(&rust_logo, c.transform.trans(150.0, 150.0), g);
+                });
+            });
+            target.finish().unwrap();
+        }
+    }
+}
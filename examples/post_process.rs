@@ -0,0 +1,95 @@
+extern crate glium;
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium::Program;
+use glium_graphics::{
+    Flip, Glium2d, GliumWindow, Material, OpenGL, PostProcess, Texture, TextureSettings,
+};
+use piston::event_loop::EventLoop;
+use piston::input::RenderEvent;
+use piston::window::WindowSettings;
+
+const VERTEX: &str = "
+#version 150 core
+in vec2 pos;
+in vec2 uv;
+out vec2 v_UV;
+
+void main() {
+    v_UV = uv;
+    gl_Position = vec4(pos, 0.0, 1.0);
+}
+";
+
+const GRAYSCALE: &str = "
+#version 150 core
+uniform sampler2D s_texture;
+in vec2 v_UV;
+out vec4 o_Color;
+
+void main() {
+    vec4 texel = texture(s_texture, v_UV);
+    float gray = dot(texel.rgb, vec3(0.2126, 0.7152, 0.0722));
+    o_Color = vec4(vec3(gray), texel.a);
+}
+";
+
+const VIGNETTE: &str = "
+#version 150 core
+uniform sampler2D s_texture;
+uniform vec2 resolution;
+in vec2 v_UV;
+out vec4 o_Color;
+
+void main() {
+    vec2 d = gl_FragCoord.xy / resolution - 0.5;
+    o_Color = texture(s_texture, v_UV) * (1.0 - dot(d, d));
+}
+";
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (300, 300);
+    let ref mut window: GliumWindow = WindowSettings::new("glium_graphics: post_process", [w, h])
+        .exit_on_esc(true)
+        .graphics_api(opengl)
+        .build()
+        .unwrap();
+
+    let rust_logo = Texture::from_path(
+        window,
+        "assets/rust.png",
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+    let grayscale = Program::from_source(window, VERTEX, GRAYSCALE, None).unwrap();
+    let vignette = Program::from_source(window, VERTEX, VIGNETTE, None).unwrap();
+    let mut post = PostProcess::new()
+        .pass(Material::new(grayscale))
+        .pass(Material::new(vignette));
+
+    let mut g2d = Glium2d::new(opengl, window);
+    window.set_lazy(true);
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(args) = e.render_args() {
+            let mut target = window.draw();
+            post.draw(&mut g2d, window, &mut target, args.viewport(), |c, g| {
+                clear(color::WHITE, g);
+                rectangle(
+                    [1.0, 0.0, 0.0, 1.0],
+                    [0.0, 0.0, 100.0, 100.0],
+                    c.transform,
+                    g,
+                );
+                image(&rust_logo, c.transform.trans(100.0, 100.0), g);
+            })
+            .unwrap();
+            target.finish().unwrap();
+        }
+    }
+}
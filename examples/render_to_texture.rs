@@ -0,0 +1,64 @@
+extern crate glium;
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium_graphics::{Flip, Glium2d, GliumWindow, OpenGL, Texture, TextureSettings};
+use piston::event_loop::EventLoop;
+use piston::input::{Button, Key, PressEvent, RenderEvent};
+use piston::window::WindowSettings;
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (300, 300);
+    let ref mut window: GliumWindow =
+        WindowSettings::new("glium_graphics: render_to_texture", [w, h])
+            .exit_on_esc(true)
+            .graphics_api(opengl)
+            .build()
+            .unwrap();
+
+    let rust_logo = Texture::from_path(
+        window,
+        "assets/rust.png",
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+    let minimap = Texture::empty_with_size(window, 100, 100, &TextureSettings::new()).unwrap();
+
+    let mut g2d = Glium2d::new(opengl, window);
+    g2d.draw_to_texture(window, &minimap, |c, g| {
+        use graphics::*;
+
+        clear([0.0, 0.0, 1.0, 1.0], g);
+        rectangle([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 50.0, 50.0], c.transform, g);
+        image(&rust_logo, c.transform.scale(0.5, 0.5), g);
+    })
+    .unwrap();
+
+    window.set_lazy(true);
+    let mut screenshot = false;
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(Button::Keyboard(Key::S)) = e.press_args() {
+            screenshot = true;
+        }
+        if let Some(args) = e.render_args() {
+            let mut target = window.draw();
+            g2d.draw(&mut target, args.viewport(), |c, g| {
+                clear(color::WHITE, g);
+                image(&minimap, c.transform.trans(100.0, 100.0), g);
+            });
+            // Copy the frame before it is shown.
+            if screenshot {
+                let frame =
+                    Texture::from_surface(window, &target, &TextureSettings::new()).unwrap();
+                frame.save("screenshot.png").unwrap();
+                screenshot = false;
+            }
+            target.finish().unwrap();
+        }
+    }
+}
@@ -0,0 +1,63 @@
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium_graphics::{
+    Flip, Glium2d, GliumWindow, OpenGL, Sprite, SpriteBatch, Texture, TextureSettings,
+};
+use piston::input::{RenderEvent, UpdateEvent};
+use piston::window::WindowSettings;
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (600, 600);
+    let ref mut window: GliumWindow = WindowSettings::new("glium_graphics: sprites", [w, h])
+        .exit_on_esc(true)
+        .graphics_api(opengl)
+        .build()
+        .unwrap();
+
+    let rust_logo = Texture::from_path(
+        window,
+        "assets/rust.png",
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+    let mut batch = SpriteBatch::new();
+    let mut time = 0.0;
+
+    let mut g2d = Glium2d::new(opengl, window);
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(args) = e.update_args() {
+            time += args.dt;
+        }
+        if let Some(args) = e.render_args() {
+            let c = Context::new_viewport(args.viewport());
+            batch.clear();
+            for i in 0..10_000 {
+                let x = (i % 100) as f64 * 6.0;
+                let y = (i / 100) as f64 * 6.0;
+                let transform = c
+                    .transform
+                    .trans(x, y)
+                    .rot_rad(time + i as f64 * 0.01)
+                    .zoom(0.1);
+                let alpha = ((time + x * 0.01).sin() * 0.5 + 0.5) as f32;
+                batch.push(
+                    &rust_logo,
+                    Sprite::new([0.0, 0.0, 144.0, 144.0], transform).color([1.0, 1.0, 1.0, alpha]),
+                );
+            }
+
+            let mut target = window.draw();
+            g2d.draw(&mut target, args.viewport(), |c, g| {
+                clear(color::WHITE, g);
+                g.draw_sprites(&c.draw_state, &batch);
+            });
+            target.finish().unwrap();
+        }
+    }
+}
@@ -0,0 +1,70 @@
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium_graphics::{Flip, Glium2d, GliumWindow, OpenGL, Texture, TextureSettings};
+use piston::input::{RenderEvent, UpdateEvent};
+use piston::window::WindowSettings;
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (300, 300);
+    let ref mut window: GliumWindow = WindowSettings::new("glium_graphics: static_mesh", [w, h])
+        .exit_on_esc(true)
+        .graphics_api(opengl)
+        .build()
+        .unwrap();
+
+    let rust_logo = Texture::from_path(
+        window,
+        "assets/rust.png",
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+    let mut g2d = Glium2d::new(opengl, window);
+
+    // Tessellate the shapes once, centered at the origin.
+    let badge = g2d
+        .record_static_mesh(|c, g| {
+            use graphics::*;
+
+            for i in 0..12 {
+                let r = 70.0 - i as f64 * 5.0;
+                let shade = if i % 2 == 0 { 0.9 } else { 0.6 };
+                ellipse(
+                    [shade, shade, shade, 1.0],
+                    [-r, -r, 2.0 * r, 2.0 * r],
+                    c.transform,
+                    g,
+                );
+            }
+            image(&rust_logo, c.transform.trans(-72.0, -72.0), g);
+        })
+        .unwrap();
+    let mut time = 0.0;
+
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(args) = e.update_args() {
+            time += args.dt;
+        }
+        if let Some(args) = e.render_args() {
+            let mut target = window.draw();
+            g2d.draw(&mut target, args.viewport(), |c, g| {
+                clear(color::WHITE, g);
+                for i in 0..4 {
+                    let transform = c
+                        .transform
+                        .trans(75.0 + (i % 2) as f64 * 150.0, 75.0 + (i / 2) as f64 * 150.0)
+                        .rot_rad(time * (i + 1) as f64 * 0.5)
+                        .zoom(0.9);
+                    let tint = [1.0, 1.0 - i as f32 * 0.2, 1.0 - i as f32 * 0.2, 1.0];
+                    g.draw_static_mesh(badge, transform, &tint);
+                }
+            });
+            target.finish().unwrap();
+        }
+    }
+}
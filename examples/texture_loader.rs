@@ -0,0 +1,71 @@
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium_graphics::{Flip, Glium2d, GliumWindow, OpenGL, Texture, TextureLoader, TextureSettings};
+use piston::input::RenderEvent;
+use piston::window::WindowSettings;
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (300, 300);
+    let ref mut window: GliumWindow = WindowSettings::new("glium_graphics: texture_loader", [w, h])
+        .exit_on_esc(true)
+        .graphics_api(opengl)
+        .build()
+        .unwrap();
+
+    // Decode an embedded image right away.
+    let rust_logo = Texture::from_bytes(
+        window,
+        include_bytes!("../assets/rust.png"),
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+
+    // Decode the other images in the background while drawing.
+    let mut loader = TextureLoader::new(2);
+    loader
+        .load_path(
+            0,
+            "assets/rust-white.png",
+            Flip::None,
+            &TextureSettings::new(),
+        )
+        .unwrap();
+    loader
+        .load_bytes(
+            1,
+            &include_bytes!("../assets/rust.png")[..],
+            Flip::Vertical,
+            &TextureSettings::new(),
+        )
+        .unwrap();
+    let mut loaded = [None, None];
+
+    let mut g2d = Glium2d::new(opengl, window);
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(args) = e.render_args() {
+            for (i, texture) in loader.upload(window) {
+                loaded[i] = Some(texture.unwrap());
+            }
+
+            let mut target = window.draw();
+            g2d.draw(&mut target, args.viewport(), |c, g| {
+                clear([0.5, 0.5, 0.5, 1.0], g);
+                image(&rust_logo, c.transform.zoom(0.5), g);
+                for (i, texture) in loaded.iter().enumerate() {
+                    if let Some(ref texture) = *texture {
+                        let x = 150.0 * (i % 2) as f64;
+                        let y = 150.0 * i.div_ceil(2) as f64;
+                        image(texture, c.transform.trans(x, y).zoom(0.5), g);
+                    }
+                }
+            });
+            target.finish().unwrap();
+        }
+    }
+}
@@ -0,0 +1,64 @@
+extern crate glium_graphics;
+extern crate graphics;
+extern crate piston;
+
+use glium_graphics::{Flip, Glium2d, GliumWindow, OpenGL, Texture, TextureSettings, Tilemap};
+use piston::input::{RenderEvent, UpdateEvent};
+use piston::window::WindowSettings;
+
+fn main() {
+    let opengl = OpenGL::V3_2;
+    let (w, h) = (600, 600);
+    let ref mut window: GliumWindow = WindowSettings::new("glium_graphics: tilemap", [w, h])
+        .exit_on_esc(true)
+        .graphics_api(opengl)
+        .build()
+        .unwrap();
+
+    // Use the 144x144 logo as a tileset of 4x4 tiles.
+    let tileset = Texture::from_path(
+        window,
+        "assets/rust.png",
+        Flip::None,
+        &TextureSettings::new(),
+    )
+    .unwrap();
+    let (columns, rows) = (500, 500);
+    let tiles = (0..columns * rows)
+        .map(|i| {
+            if i % 7 == 0 {
+                None
+            } else {
+                Some((i * 31 % 16) as u32)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut tilemap = Tilemap::new(window, tileset, [36, 36], [columns, rows], &tiles).unwrap();
+    let mut time = 0.0;
+    let mut changed = 0;
+
+    let mut g2d = Glium2d::new(opengl, window);
+    while let Some(e) = window.next() {
+        use graphics::*;
+
+        if let Some(args) = e.update_args() {
+            time += args.dt;
+            // Change one tile every update.
+            changed = (changed + 1) % 20;
+            let tile = tilemap.get_tile(changed, 2).map(|id| (id + 1) % 16);
+            tilemap.set_tile(changed, 2, tile);
+        }
+        if let Some(args) = e.render_args() {
+            let mut target = window.draw();
+            g2d.draw(&mut target, args.viewport(), |c, g| {
+                clear(color::WHITE, g);
+                let camera = c
+                    .transform
+                    .trans(-time * 40.0, -time * 20.0)
+                    .zoom(0.5 + (time * 0.3).sin().abs());
+                g.draw_tilemap(&c.draw_state, &color::WHITE, &mut tilemap, camera);
+            });
+            target.finish().unwrap();
+        }
+    }
+}
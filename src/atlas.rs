@@ -0,0 +1,277 @@
+use glium::backend::Facade;
+use graphics::Image;
+use image::{self, RgbaImage};
+use std::path::Path;
+use texture::{Format, TextureSettings, UpdateTexture};
+use {Glium2dError, Texture};
+
+/// An image packed into an atlas.
+#[derive(Clone)]
+pub struct AtlasRegion {
+    /// The atlas page containing the image.
+    pub texture: Texture,
+    /// The index of the atlas page.
+    pub page: usize,
+    /// The location of the image in the page, `[x, y, w, h]` in pixels.
+    pub src_rect: [f64; 4],
+}
+
+impl AtlasRegion {
+    /// Returns an image drawing the region at its size in pixels.
+    pub fn image(&self) -> Image {
+        Image::new().src_rect(self.src_rect)
+    }
+}
+
+// An atlas page.
+struct Page {
+    texture: Texture,
+    shelves: Shelves,
+}
+
+// Packs rows of images into a square, filled from the top.
+#[derive(Default)]
+struct Shelves {
+    // The left side of the free space in the current row.
+    x: u32,
+    // The top of the current row.
+    y: u32,
+    // The height of the current row.
+    row_height: u32,
+}
+
+impl Shelves {
+    // Reserves space, returning the top left corner.
+    fn allocate(&mut self, size: u32, w: u32, h: u32) -> Option<[u32; 2]> {
+        let (x, y, row_height) = if self.x + w > size {
+            (0, self.y + self.row_height, 0)
+        } else {
+            (self.x, self.y, self.row_height)
+        };
+        if x + w > size || y + h > size {
+            return None;
+        }
+        self.x = x + w;
+        self.y = y;
+        self.row_height = row_height.max(h);
+        Some([x, y])
+    }
+}
+
+/// Packs many images into a few textures, so they can be drawn in one batch.
+///
+/// Images are surrounded by padding filled with their edge pixels,
+/// which prevents neighbours from bleeding in with linear filtering.
+/// New pages are created when the existing ones are full,
+/// and images can be inserted at any time.
+pub struct Atlas {
+    size: u32,
+    padding: u32,
+    settings: TextureSettings,
+    pages: Vec<Page>,
+}
+
+impl Atlas {
+    /// Creates a new atlas with square pages of a size in pixels.
+    pub fn new(size: u32) -> Atlas {
+        Atlas {
+            size,
+            padding: 1,
+            settings: TextureSettings::new(),
+            pages: vec![],
+        }
+    }
+
+    /// Gets the padding in pixels around each image.
+    pub fn get_padding(&self) -> u32 {
+        self.padding
+    }
+
+    /// Sets the padding in pixels around each image.
+    ///
+    /// Applies to images inserted after the change.
+    pub fn set_padding(&mut self, padding: u32) {
+        self.padding = padding;
+    }
+
+    /// Sets the padding in pixels around each image.
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.set_padding(padding);
+        self
+    }
+
+    /// Gets the texture settings of new pages.
+    pub fn get_texture_settings(&self) -> &TextureSettings {
+        &self.settings
+    }
+
+    /// Sets the texture settings of new pages.
+    pub fn set_texture_settings(&mut self, settings: TextureSettings) {
+        self.settings = settings;
+    }
+
+    /// Sets the texture settings of new pages.
+    pub fn texture_settings(mut self, settings: TextureSettings) -> Self {
+        self.set_texture_settings(settings);
+        self
+    }
+
+    /// Returns the textures of the pages.
+    pub fn pages(&self) -> Vec<&Texture> {
+        self.pages.iter().map(|page| &page.texture).collect()
+    }
+
+    /// Inserts an image.
+    pub fn insert<F>(
+        &mut self,
+        factory: &mut F,
+        img: &RgbaImage,
+    ) -> Result<AtlasRegion, Glium2dError>
+    where
+        F: Facade,
+    {
+        let (w, h) = img.dimensions();
+        let (pw, ph) = (w + 2 * self.padding, h + 2 * self.padding);
+        if pw > self.size || ph > self.size {
+            return Err(Glium2dError::TooLargeForAtlas([w, h]));
+        }
+
+        let size = self.size;
+        let found = self
+            .pages
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, page)| page.shelves.allocate(size, pw, ph).map(|pos| (i, pos)))
+            .next();
+        let (index, pos) = match found {
+            Some(found) => found,
+            None => {
+                let texture = Texture::empty_with_size(factory, size, size, &self.settings)?;
+                let mut page = Page {
+                    texture,
+                    shelves: Shelves::default(),
+                };
+                let pos = page.shelves.allocate(size, pw, ph).unwrap();
+                self.pages.push(page);
+                (self.pages.len() - 1, pos)
+            }
+        };
+
+        let texture = &mut self.pages[index].texture;
+        if w > 0 && h > 0 {
+            let padded = extrude(img, self.padding);
+            UpdateTexture::update(texture, factory, Format::Rgba8, &padded, pos, [pw, ph])?;
+        }
+        Ok(AtlasRegion {
+            texture: texture.clone(),
+            page: index,
+            src_rect: [
+                (pos[0] + self.padding) as f64,
+                (pos[1] + self.padding) as f64,
+                w as f64,
+                h as f64,
+            ],
+        })
+    }
+
+    /// Inserts many images, returning their regions in the same order.
+    ///
+    /// Taller images are packed first, which wastes less space
+    /// than inserting the images one by one.
+    pub fn insert_all<F>(
+        &mut self,
+        factory: &mut F,
+        images: &[RgbaImage],
+    ) -> Result<Vec<AtlasRegion>, Glium2dError>
+    where
+        F: Facade,
+    {
+        let mut regions = vec![None; images.len()];
+        for i in tallest_first(images) {
+            regions[i] = Some(self.insert(factory, &images[i])?);
+        }
+        Ok(regions.into_iter().map(|region| region.unwrap()).collect())
+    }
+
+    /// Inserts an image loaded from a path.
+    pub fn insert_path<F, P>(&mut self, factory: &mut F, path: P) -> Result<AtlasRegion, String>
+    where
+        F: Facade,
+        P: AsRef<Path>,
+    {
+        let img = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
+        self.insert(factory, &img).map_err(|e| e.to_string())
+    }
+}
+
+// Returns the indices of images from the tallest to the shortest.
+fn tallest_first(images: &[RgbaImage]) -> Vec<usize> {
+    let mut order = (0..images.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| ::std::cmp::Reverse(images[i].height()));
+    order
+}
+
+// Surrounds an image with padding repeating its edge pixels.
+fn extrude(img: &RgbaImage, padding: u32) -> RgbaImage {
+    let (w, h) = img.dimensions();
+    RgbaImage::from_fn(w + 2 * padding, h + 2 * padding, |x, y| {
+        let x = x.max(padding).min(w + padding - 1) - padding;
+        let y = y.max(padding).min(h + padding - 1) - padding;
+        *img.get_pixel(x, y)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn allocate_fills_rows() {
+        let mut shelves = Shelves::default();
+        assert_eq!(shelves.allocate(10, 4, 3), Some([0, 0]));
+        assert_eq!(shelves.allocate(10, 4, 5), Some([4, 0]));
+        // Does not fit in the row, so starts a new one below the tallest image.
+        assert_eq!(shelves.allocate(10, 4, 2), Some([0, 5]));
+        assert_eq!(shelves.allocate(10, 6, 2), Some([4, 5]));
+    }
+
+    #[test]
+    fn allocate_overflow() {
+        let mut shelves = Shelves::default();
+        assert_eq!(shelves.allocate(10, 11, 1), None);
+        assert_eq!(shelves.allocate(10, 10, 8), Some([0, 0]));
+        assert_eq!(shelves.allocate(10, 5, 3), None);
+        assert_eq!(shelves.allocate(10, 5, 2), Some([0, 8]));
+    }
+
+    #[test]
+    fn tallest_images_first() {
+        let images = [2, 5, 1, 5, 3]
+            .iter()
+            .map(|&h| RgbaImage::new(1, h))
+            .collect::<Vec<_>>();
+        assert_eq!(tallest_first(&images), vec![1, 3, 4, 0, 2]);
+    }
+
+    #[test]
+    fn extrude_edges() {
+        let img = RgbaImage::from_fn(2, 2, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        let padded = extrude(&img, 2);
+        assert_eq!(padded.dimensions(), (6, 6));
+        let pixel = |x, y| padded.get_pixel(x, y).0;
+        // The image in the middle.
+        assert_eq!(pixel(2, 2), [0, 0, 0, 255]);
+        assert_eq!(pixel(3, 3), [1, 1, 0, 255]);
+        // Edges repeat the nearest pixel of the image.
+        assert_eq!(pixel(0, 3), [0, 1, 0, 255]);
+        assert_eq!(pixel(5, 2), [1, 0, 0, 255]);
+        assert_eq!(pixel(2, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(3, 5), [1, 1, 0, 255]);
+        // Corners repeat the corner pixels.
+        assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(5, 0), [1, 0, 0, 255]);
+        assert_eq!(pixel(0, 5), [0, 1, 0, 255]);
+        assert_eq!(pixel(5, 5), [1, 1, 0, 255]);
+    }
+}
@@ -1,155 +1,1022 @@
-use glium::backend::Facade;
-use glium::index::{NoIndices, PrimitiveType};
-use glium::{Frame, Program, Surface, VertexBuffer};
-use graphics::color::gamma_srgb_to_linear;
-use graphics::{self, DrawState, Graphics, Viewport};
+use glium::backend::{Context, Facade};
+use glium::draw_parameters::TimeElapsedQuery;
+use glium::framebuffer::{DepthStencilRenderBuffer, SimpleFrameBuffer};
+use glium::index::{IndexBuffer, IndicesSource, NoIndices, PrimitiveType};
+use glium::program::ProgramCreationInput;
+use glium::texture::{
+    DepthStencilFormat, DepthStencilTexture2dMultisample, MipmapsOption, SrgbFormat, SrgbTexture2d,
+    SrgbTexture2dMultisample,
+};
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, Sampler};
+use glium::{Blend, BlitTarget, DrawError, DrawParameters, Program, Surface, VertexBuffer};
+use graphics::color::{gamma_linear_to_srgb, gamma_srgb_to_linear};
+use graphics::math::{transform_pos, Matrix2d};
+use graphics::{self, DrawState, Graphics, ImageSize, Viewport};
+#[cfg(feature = "image")]
+use image::RgbaImage;
 use shader_version::glsl::GLSL;
 use shader_version::{OpenGL, Shaders};
-use Texture;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use std::{mem, slice};
+#[cfg(feature = "image")]
+use texture::TextureSettings;
+use {
+    AdvancedBlend, BlendEquation, ColorSpace, DisplayList, FrameStats, Glium2dError, Material,
+    MeshRecorder, ShaderVersion, Sprite, SpriteBatch, StaticMesh, Texture, TextureStorage, Tilemap,
+};
 
+use display_list::Command;
 use draw_state;
+use glsl;
+use material::WithMaterial;
+use static_mesh::{MeshData, MeshPart};
+use vertex_stream::VertexStream;
 
 const CHUNKS: usize = 100;
 
+// The number of sprites drawn per instanced draw call.
+const SPRITES: usize = CHUNKS * graphics::BACK_END_MAX_VERTEX_COUNT / 4;
+
+// The triangles of a quad uploaded as four vertices,
+// in the order rectangles are triangulated by `graphics`.
+pub(crate) const QUAD_INDICES: [u32; 6] = [0, 1, 2, 1, 3, 2];
+
+// The number of textures bound at once for textured rendering.
+const TEXTURE_SLOTS: usize = 4;
+
+// The number of converted draw states kept for reuse.
+const DRAW_PARAMETERS_CACHE: usize = 32;
+
+// Extends the draw parameters converted from a draw state.
+type DrawParametersHook = Box<dyn Fn(&DrawState, &mut DrawParameters<'static>)>;
+
+// The corners of a unit quad, drawn as a triangle strip or with the quad indices.
+pub(crate) const QUAD_CORNERS: [[f32; 2]; 4] = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+
+// Calls a surface method on the multisampled target when rendering with MSAA,
+// and on the surface otherwise.
+// Once the surface is used, the frame keeps drawing to it,
+// since resolving the multisampled target would replace what was drawn.
+macro_rules! with_target {
+    ($g:expr, $target:ident => $e:expr) => {{
+        $g.pending_msaa = None;
+        match $g.msaa {
+            Some(ref mut $target) => $e,
+            None => {
+                let $target = &mut *$g.surface;
+                $e
+            }
+        }
+    }};
+}
+
+// Collects the vertex shader sources of a shader module.
+macro_rules! vertex {
+    ($module:ident) => {
+        [
+            $module::VERTEX_GLSL_120,
+            $module::VERTEX_GLSL_150_CORE,
+            $module::VERTEX_GLSL_120_WEBGL,
+            $module::VERTEX_GLSL_150_CORE_WEBGL,
+        ]
+    };
+}
+
+// Collects the fragment shader sources of a shader module.
+macro_rules! fragment {
+    ($module:ident) => {
+        [
+            $module::FRAGMENT_GLSL_120,
+            $module::FRAGMENT_GLSL_150_CORE,
+            $module::FRAGMENT_GLSL_120_WEBGL,
+            $module::FRAGMENT_GLSL_150_CORE_WEBGL,
+        ]
+    };
+}
+
 #[derive(Copy, Clone)]
-struct PlainVertex {
-    color: [f32; 4],
-    pos: [f32; 2],
+pub(crate) struct PlainVertex {
+    pub color: [f32; 4],
+    pub pos: [f32; 2],
 }
 
 implement_vertex!(PlainVertex, color, pos);
 
 #[derive(Copy, Clone)]
-struct TexturedVertex {
-    pos: [f32; 2],
-    uv: [f32; 2],
+pub(crate) struct TexturedVertex {
+    pub pos: [f32; 2],
+    pub uv: [f32; 2],
 }
 
 implement_vertex!(TexturedVertex, pos, uv);
 
 #[derive(Copy, Clone)]
-struct TexturedColorVertex {
+pub(crate) struct TexturedColorVertex {
+    pub pos: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+implement_vertex!(TexturedColorVertex, pos, uv, color);
+
+#[derive(Copy, Clone)]
+struct SlotVertex {
     pos: [f32; 2],
     uv: [f32; 2],
     color: [f32; 4],
+    // The index of the texture slot.
+    slot: f32,
 }
 
-implement_vertex!(TexturedColorVertex, pos, uv, color);
+implement_vertex!(SlotVertex, pos, uv, color, slot);
+
+#[derive(Copy, Clone)]
+struct SpriteCorner {
+    corner: [f32; 2],
+}
+
+implement_vertex!(SpriteCorner, corner);
+
+#[derive(Copy, Clone)]
+struct SpriteInstance {
+    // The rows of the transform, scaled to the size of the sprite.
+    transform_x: [f32; 3],
+    transform_y: [f32; 3],
+    // The texture coordinates of the top left corner, and the size.
+    src_uv: [f32; 4],
+    color: [f32; 4],
+}
+
+implement_vertex!(SpriteInstance, transform_x, transform_y, src_uv, color);
 
 /// The resources needed for rendering 2D.
 pub struct Glium2d {
-    // The offset in vertices for colored rendering.
-    colored_offset: usize,
+    // How the vertices for colored rendering form triangles.
+    colored_batch: Batch,
     // The current draw state for colored rendering.
     colored_draw_state: DrawState,
-    plain_buffer: VertexBuffer<PlainVertex>,
-    textured_buffer: VertexBuffer<TexturedVertex>,
-    textured_color_buffer: VertexBuffer<TexturedColorVertex>,
+    // How the vertices for textured rendering form triangles.
+    textured_batch: Batch,
+    // The current draw state for textured rendering.
+    textured_draw_state: DrawState,
+    // The current color for textured rendering with a material.
+    textured_color: [f32; 4],
+    // The textures bound to the slots for textured rendering.
+    textured_textures: Vec<Texture>,
+    // How the vertices for textured color rendering form triangles.
+    textured_color_batch: Batch,
+    // The current draw state for textured color rendering.
+    textured_color_draw_state: DrawState,
+    // The current texture for textured color rendering.
+    textured_color_texture: Option<Texture>,
+    // The first error from drawing, reported at the end of the frame.
+    draw_error: Option<DrawError>,
+    // The material used instead of the built-in shaders.
+    material: Option<Material>,
+    // The blend equation used instead of the one of the draw state.
+    blend: Option<Blend>,
+    // The layer drawn to with an advanced blend mode, and the copy of the target it blends with.
+    blend_targets: Option<(SrgbTexture2d, SrgbTexture2d, DepthStencilRenderBuffer)>,
+    // The number of samples for multisample anti-aliasing, disabled below 2.
+    samples: u8,
+    // The number of samples of the surfaces drawn to with `draw`.
+    frame_samples: u8,
+    // The multisampled color and depth stencil targets, reused between frames.
+    msaa_targets: Option<(SrgbTexture2dMultisample, DepthStencilTexture2dMultisample)>,
+    // The depth stencil buffer for rendering into textures, reused between calls.
+    texture_depth_stencil: Option<DepthStencilRenderBuffer>,
+    // The statistics of the current frame, when enabled.
+    stats: Option<FrameStats>,
+    // Whether offscreen draws have added to the statistics of a frame that has not ended.
+    stats_open: bool,
+    // The textures of the last textured draw call, when collecting statistics.
+    stats_textures: Vec<Texture>,
+    // Measures the GPU time of the current frame.
+    time_query: Option<TimeElapsedQuery>,
+    // Measures the GPU time of an earlier frame that is not done yet.
+    pending_time_query: Option<TimeElapsedQuery>,
+    // The GPU time of the last measured frame.
+    gpu_time: Option<Duration>,
+    // The graphics calls of the current frame, when recording.
+    display_list: Option<DisplayList>,
+    // The color space colors are blended in.
+    color_space: ColorSpace,
+    // Whether colors and blending use premultiplied alpha.
+    premultiplied_alpha: bool,
+    // Recently converted draw states, with the oldest first.
+    draw_parameters_cache: RefCell<Vec<(DrawState, DrawParameters<'static>)>>,
+    draw_parameters_hook: Option<DrawParametersHook>,
+    context: Rc<Context>,
+    plain_buffer: VertexStream<PlainVertex>,
+    textured_buffer: VertexStream<SlotVertex>,
+    textured_color_buffer: VertexStream<TexturedColorVertex>,
+    // Indices drawing every four vertices as a quad, shared by all kinds of rendering.
+    quad_indices: IndexBuffer<u32>,
+    // Indices of the mesh being drawn, or of a batch mixing quads and triangles.
+    mesh_indices: IndexBuffer<u32>,
+    // The generated indices of a batch mixing quads and triangles.
+    batch_indices: Vec<u32>,
+    // The quad drawn for every sprite instance.
+    sprite_corners: VertexBuffer<SpriteCorner>,
+    sprite_buffer: VertexStream<SpriteInstance>,
+    // Whether sprites are drawn with instancing instead of as textured triangles.
+    instancing: bool,
+    shader_sprite: Program,
+    shader_sprite_alpha: Program,
+    // Geometry recorded once, drawn with a transform.
+    static_meshes: Vec<Option<MeshData>>,
+    shader_transformed_color: Program,
+    shader_transformed_texture: Program,
+    shader_transformed_texture_alpha: Program,
+    shader_transformed_texture_color: Program,
+    shader_transformed_texture_color_alpha: Program,
     shader_texture_color: Program,
-    shader_texture: Program,
+    shader_texture_slots: Program,
+    shader_texture_color_alpha: Program,
     shader_color: Program,
+    shader_blend: Program,
 }
 
 impl Glium2d {
     /// Creates a new `Glium2d`.
+    ///
+    /// Panics if the shaders or buffers can not be created,
+    /// see `Glium2d::try_new` for a fallible version.
     pub fn new<W>(opengl: OpenGL, window: &W) -> Glium2d
     where
         W: Facade,
     {
+        Glium2d::try_new(opengl, window).expect("failed to initialize Glium2d")
+    }
+
+    /// Creates a new `Glium2d`, returning an error on failure.
+    ///
+    /// This makes it possible to fall back to another OpenGL version
+    /// when the driver does not support the requested one.
+    pub fn try_new<W>(opengl: OpenGL, window: &W) -> Result<Glium2d, Glium2dError>
+    where
+        W: Facade,
+    {
+        Glium2d::with_shader_version(ShaderVersion::Glsl(opengl.to_glsl()), window)
+    }
+
+    /// Creates a new `Glium2d`, picking the shaders and color space from the context.
+    ///
+    /// This supports OpenGL ES contexts in addition to desktop OpenGL,
+    /// and framebuffers without sRGB support.
+    pub fn detect<W>(window: &W) -> Result<Glium2d, Glium2dError>
+    where
+        W: Facade,
+    {
+        match ShaderVersion::detect(window) {
+            Some(version) => Glium2d::with_color_space(version, ColorSpace::detect(window), window),
+            None => Err(Glium2dError::NoSupportedShaders),
+        }
+    }
+
+    /// Creates a new `Glium2d` using shaders for a shading language version.
+    ///
+    /// Blends in linear color space.
+    pub fn with_shader_version<W>(
+        version: ShaderVersion,
+        window: &W,
+    ) -> Result<Glium2d, Glium2dError>
+    where
+        W: Facade,
+    {
+        Glium2d::with_color_space(version, ColorSpace::Linear, window)
+    }
+
+    /// Creates a new `Glium2d` using shaders for a shading language version,
+    /// blending in a color space.
+    pub fn with_color_space<W>(
+        version: ShaderVersion,
+        color_space: ColorSpace,
+        window: &W,
+    ) -> Result<Glium2d, Glium2dError>
+    where
+        W: Facade,
+    {
+        use glsl::{
+            blend, sprite, textured_alpha, textured_color_alpha, textured_slots,
+            transformed_colored, transformed_textured, transformed_textured_color,
+        };
         use shaders::{colored, textured, textured_color};
 
-        let src = |bytes| unsafe { ::std::str::from_utf8_unchecked(bytes) };
-        let glsl = opengl.to_glsl();
+        let program = |vertex, fragment| program(window, version, color_space, vertex, fragment);
 
-        let plain_buffer =
-            VertexBuffer::empty_dynamic(window, CHUNKS * graphics::BACK_END_MAX_VERTEX_COUNT)
-                .unwrap();
-        Glium2d {
-            colored_offset: 0,
+        let size = CHUNKS * graphics::BACK_END_MAX_VERTEX_COUNT;
+        let sprite_corners = VertexBuffer::new(
+            window,
+            &QUAD_CORNERS
+                .iter()
+                .map(|&corner| SpriteCorner { corner })
+                .collect::<Vec<_>>(),
+        )?;
+        let instancing = sprite_corners.per_instance().is_ok();
+
+        Ok(Glium2d {
+            colored_batch: Batch::Triangles,
             colored_draw_state: Default::default(),
-            plain_buffer: plain_buffer,
-            textured_buffer: VertexBuffer::empty_dynamic(
-                window,
-                graphics::BACK_END_MAX_VERTEX_COUNT,
-            )
-            .unwrap(),
-            textured_color_buffer: VertexBuffer::empty_dynamic(
-                window,
-                graphics::BACK_END_MAX_VERTEX_COUNT,
-            )
-            .unwrap(),
-            shader_texture_color: Program::from_source(
-                window,
-                Shaders::new()
-                    .set(GLSL::V1_20, src(textured_color::VERTEX_GLSL_120))
-                    .set(GLSL::V1_50, src(textured_color::VERTEX_GLSL_150_CORE))
-                    .get(glsl)
-                    .unwrap(),
-                Shaders::new()
-                    .set(GLSL::V1_20, src(textured_color::FRAGMENT_GLSL_120))
-                    .set(GLSL::V1_50, src(textured_color::FRAGMENT_GLSL_150_CORE))
-                    .get(glsl)
-                    .unwrap(),
-                None,
-            )
-            .ok()
-            .expect("failed to initialize textured color shader"),
-            shader_texture: Program::from_source(
-                window,
-                Shaders::new()
-                    .set(GLSL::V1_20, src(textured::VERTEX_GLSL_120))
-                    .set(GLSL::V1_50, src(textured::VERTEX_GLSL_150_CORE))
-                    .get(glsl)
-                    .unwrap(),
-                Shaders::new()
-                    .set(GLSL::V1_20, src(textured::FRAGMENT_GLSL_120))
-                    .set(GLSL::V1_50, src(textured::FRAGMENT_GLSL_150_CORE))
-                    .get(glsl)
-                    .unwrap(),
-                None,
-            )
-            .ok()
-            .expect("failed to initialize textured shader"),
-            shader_color: Program::from_source(
+            textured_batch: Batch::Triangles,
+            textured_draw_state: Default::default(),
+            textured_color: [1.0; 4],
+            textured_textures: Vec::with_capacity(TEXTURE_SLOTS),
+            textured_color_batch: Batch::Triangles,
+            textured_color_draw_state: Default::default(),
+            textured_color_texture: None,
+            draw_error: None,
+            material: None,
+            blend: None,
+            blend_targets: None,
+            samples: 0,
+            frame_samples: 0,
+            msaa_targets: None,
+            texture_depth_stencil: None,
+            stats: None,
+            stats_open: false,
+            stats_textures: vec![],
+            time_query: None,
+            pending_time_query: None,
+            gpu_time: None,
+            display_list: None,
+            color_space,
+            premultiplied_alpha: false,
+            draw_parameters_cache: RefCell::new(Vec::with_capacity(DRAW_PARAMETERS_CACHE)),
+            draw_parameters_hook: None,
+            context: window.get_context().clone(),
+            plain_buffer: VertexStream::new(window, size)?,
+            textured_buffer: VertexStream::new(window, size)?,
+            textured_color_buffer: VertexStream::new(window, size)?,
+            quad_indices: IndexBuffer::new(window, PrimitiveType::TrianglesList, &{
+                (0..size as u32 / 4)
+                    .flat_map(|q| QUAD_INDICES.iter().map(move |i| 4 * q + i))
+                    .collect::<Vec<_>>()
+            })?,
+            mesh_indices: IndexBuffer::empty_dynamic(
                 window,
-                Shaders::new()
-                    .set(GLSL::V1_20, src(colored::VERTEX_GLSL_120))
-                    .set(GLSL::V1_50, src(colored::VERTEX_GLSL_150_CORE))
-                    .get(glsl)
-                    .unwrap(),
-                Shaders::new()
-                    .set(GLSL::V1_20, src(colored::FRAGMENT_GLSL_120))
-                    .set(GLSL::V1_50, src(colored::FRAGMENT_GLSL_150_CORE))
-                    .get(glsl)
-                    .unwrap(),
-                None,
-            )
-            .ok()
-            .expect("failed to initialize colored shader"),
+                PrimitiveType::TrianglesList,
+                size / 4 * QUAD_INDICES.len(),
+            )?,
+            batch_indices: Vec::with_capacity(size / 4 * QUAD_INDICES.len()),
+            sprite_corners,
+            sprite_buffer: VertexStream::new(window, SPRITES)?,
+            instancing,
+            shader_sprite: program(vertex!(sprite), fragment!(textured_color))?,
+            shader_sprite_alpha: program(vertex!(sprite), fragment!(textured_color_alpha))?,
+            static_meshes: vec![],
+            shader_transformed_color: program(vertex!(transformed_colored), fragment!(colored))?,
+            shader_transformed_texture: program(
+                vertex!(transformed_textured),
+                fragment!(textured),
+            )?,
+            shader_transformed_texture_alpha: program(
+                vertex!(transformed_textured),
+                fragment!(textured_alpha),
+            )?,
+            shader_transformed_texture_color: program(
+                vertex!(transformed_textured_color),
+                fragment!(textured_color),
+            )?,
+            shader_transformed_texture_color_alpha: program(
+                vertex!(transformed_textured_color),
+                fragment!(textured_color_alpha),
+            )?,
+            shader_texture_color: program(vertex!(textured_color), fragment!(textured_color))?,
+            shader_texture_slots: program(vertex!(textured_slots), fragment!(textured_slots))?,
+            shader_texture_color_alpha: program(
+                vertex!(textured_color),
+                fragment!(textured_color_alpha),
+            )?,
+            shader_color: program(vertex!(colored), fragment!(colored))?,
+            shader_blend: program(vertex!(blend), fragment!(blend))?,
+        })
+    }
+
+    /// Gets the color space colors are blended in.
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Gets whether colors and blending use premultiplied alpha.
+    pub fn get_premultiplied_alpha(&self) -> bool {
+        self.premultiplied_alpha
+    }
+
+    /// Sets whether colors and blending use premultiplied alpha.
+    ///
+    /// Colors are multiplied with their alpha before drawing,
+    /// and the blend modes add the source instead of weighting it by alpha.
+    /// This avoids dark halos around scaled or filtered edges,
+    /// but textures must be uploaded with premultiplied alpha,
+    /// see `Texture::from_memory_premultiplied`.
+    /// Alpha textures work either way.
+    ///
+    /// Static meshes keep the colors they were recorded with.
+    pub fn set_premultiplied_alpha(&mut self, enabled: bool) {
+        self.premultiplied_alpha = enabled;
+        self.draw_parameters_cache.get_mut().clear();
+    }
+
+    /// Sets whether colors and blending use premultiplied alpha.
+    pub fn premultiplied_alpha(mut self, enabled: bool) -> Self {
+        self.set_premultiplied_alpha(enabled);
+        self
+    }
+
+    /// Gets the draw parameters converted from a draw state.
+    ///
+    /// Conversions are cached, so each recently used draw state is converted once.
+    /// Useful for drawing with glium directly, consistent with the 2D graphics.
+    pub fn get_draw_parameters(&self, draw_state: &DrawState) -> DrawParameters<'static> {
+        let mut cache = self.draw_parameters_cache.borrow_mut();
+        if let Some((_, params)) = cache.iter().find(|entry| entry.0 == *draw_state) {
+            return params.clone();
+        }
+        let mut params = draw_state::convert_draw_state(draw_state, self.premultiplied_alpha);
+        if let Some(ref hook) = self.draw_parameters_hook {
+            hook(draw_state, &mut params);
+        }
+        if cache.len() == DRAW_PARAMETERS_CACHE {
+            cache.remove(0);
+        }
+        cache.push((*draw_state, params.clone()));
+        params
+    }
+
+    /// Sets a function extending the draw parameters converted from each draw state,
+    /// for example with a depth test, a color mask or a viewport.
+    ///
+    /// The function is called when a draw state is converted,
+    /// and the result is cached, see `Glium2d::get_draw_parameters`.
+    /// Blend equations set with `GliumGraphics::with_blend` are applied afterwards.
+    pub fn set_draw_parameters_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&DrawState, &mut DrawParameters<'static>) + 'static,
+    {
+        self.draw_parameters_hook = Some(Box::new(hook));
+        self.draw_parameters_cache.get_mut().clear();
+    }
+
+    /// Removes the function extending the draw parameters.
+    pub fn remove_draw_parameters_hook(&mut self) {
+        self.draw_parameters_hook = None;
+        self.draw_parameters_cache.get_mut().clear();
+    }
+
+    /// Sets a function extending the draw parameters converted from each draw state.
+    pub fn draw_parameters_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&DrawState, &mut DrawParameters<'static>) + 'static,
+    {
+        self.set_draw_parameters_hook(hook);
+        self
+    }
+
+    /// Gets the number of samples for multisample anti-aliasing.
+    pub fn get_samples(&self) -> u8 {
+        self.samples
+    }
+
+    /// Sets the number of samples for multisample anti-aliasing.
+    ///
+    /// With 2 or more samples, graphics are rendered into a multisampled target,
+    /// which is resolved into the surface at the end of `Glium2d::draw`.
+    /// Use 0 to draw directly to the surface, which is the default.
+    ///
+    /// The resolved target replaces the surface, so it is only used
+    /// when the frame starts with `clear`, which covers what was on the surface.
+    /// Otherwise graphics are drawn directly to the surface without anti-aliasing.
+    ///
+    /// A resolved target can not be copied into a multisampled frame,
+    /// so `Glium2d::draw` draws directly to frames that are multisampled already,
+    /// see `Glium2d::set_frame_samples`.
+    pub fn set_samples(&mut self, samples: u8) {
+        self.samples = samples;
+        if samples < 2 {
+            self.msaa_targets = None;
+        }
+    }
+
+    /// Sets the number of samples for multisample anti-aliasing.
+    pub fn samples(mut self, samples: u8) -> Self {
+        self.set_samples(samples);
+        self
+    }
+
+    /// Gets the number of samples of the surfaces passed to `Glium2d::draw`.
+    pub fn get_frame_samples(&self) -> u8 {
+        self.frame_samples
+    }
+
+    /// Sets the number of samples of the surfaces passed to `Glium2d::draw`,
+    /// such as the frame of a window, see `GliumWindow::get_samples`.
+    ///
+    /// With 2 or more samples, `Glium2d::draw` draws directly to the surface,
+    /// which is anti-aliased already.
+    /// Drawing to textures still uses the samples set with `Glium2d::set_samples`.
+    /// This is 0 by default.
+    pub fn set_frame_samples(&mut self, samples: u8) {
+        self.frame_samples = samples;
+    }
+
+    /// Sets the number of samples of the surfaces passed to `Glium2d::draw`.
+    pub fn frame_samples(mut self, samples: u8) -> Self {
+        self.set_frame_samples(samples);
+        self
+    }
+
+    /// Gets whether statistics are collected for each frame.
+    pub fn get_collect_stats(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    /// Sets whether statistics are collected for each frame.
+    ///
+    /// A frame ends with a call to `Glium2d::draw` or `Glium2d::try_draw`,
+    /// and includes the offscreen draws since the previous frame,
+    /// such as `Glium2d::draw_to_texture` and the passes of `PostProcess`.
+    ///
+    /// This is disabled by default.
+    pub fn set_collect_stats(&mut self, enabled: bool) {
+        self.stats = if enabled {
+            Some(FrameStats::default())
+        } else {
+            None
+        };
+        self.stats_open = false;
+        self.stats_textures.clear();
+        self.time_query = None;
+        self.pending_time_query = None;
+        self.gpu_time = None;
+    }
+
+    /// Sets whether statistics are collected for each frame.
+    pub fn collect_stats(mut self, enabled: bool) -> Self {
+        self.set_collect_stats(enabled);
+        self
+    }
+
+    /// Returns the statistics of the last frame,
+    /// or `None` when statistics are not collected.
+    ///
+    /// After offscreen draws, this includes the frame that has not ended yet.
+    pub fn get_stats(&self) -> Option<&FrameStats> {
+        self.stats.as_ref()
+    }
+
+    /// Gets whether the graphics calls of each frame are recorded.
+    pub fn get_record_display_list(&self) -> bool {
+        self.display_list.is_some()
+    }
+
+    /// Sets whether the graphics calls of each frame are recorded.
+    ///
+    /// This is disabled by default.
+    pub fn set_record_display_list(&mut self, enabled: bool) {
+        self.display_list = if enabled {
+            Some(DisplayList::new())
+        } else {
+            None
+        };
+    }
+
+    /// Sets whether the graphics calls of each frame are recorded.
+    pub fn record_display_list(mut self, enabled: bool) -> Self {
+        self.set_record_display_list(enabled);
+        self
+    }
+
+    /// Returns the graphics calls of the last call to `Glium2d::draw`,
+    /// or `None` when they are not recorded.
+    pub fn get_display_list(&self) -> Option<&DisplayList> {
+        self.display_list.as_ref()
+    }
+
+    /// Replays a display list onto a surface.
+    ///
+    /// The vertices were recorded after the transform,
+    /// so they cover the same part of a target with the same size.
+    pub fn replay<S>(&mut self, target: &mut S, list: &DisplayList) -> Result<(), Glium2dError>
+    where
+        S: Surface,
+    {
+        let (w, h) = target.get_dimensions();
+        let viewport = Viewport {
+            rect: [0, 0, w as i32, h as i32],
+            draw_size: [w, h],
+            window_size: [w as f64, h as f64],
+        };
+        self.try_draw(target, viewport, |_, g| list.replay(g))
+    }
+
+    // Starts collecting statistics for a frame, unless offscreen draws started it already.
+    fn begin_stats(&mut self) {
+        if self.stats.is_none() || self.stats_open {
+            return;
+        }
+        self.stats_open = true;
+        // Read the GPU time of an earlier frame without waiting for it,
+        // and only measure a new frame when the earlier one is done.
+        let ready = match self.pending_time_query {
+            Some(ref query) => query.is_ready(),
+            None => false,
+        };
+        if ready {
+            let query = self.pending_time_query.take().unwrap();
+            self.gpu_time = Some(Duration::from_nanos(query.get() as u64));
+        }
+        if self.pending_time_query.is_none() {
+            self.time_query = TimeElapsedQuery::new(&self.context).ok();
+        }
+        self.stats = Some(FrameStats {
+            gpu_time: self.gpu_time,
+            ..FrameStats::default()
+        });
+    }
+
+    // Finishes collecting statistics for a frame.
+    fn end_stats(&mut self) {
+        self.stats_open = false;
+        self.stats_textures.clear();
+        if let Some(query) = self.time_query.take() {
+            self.pending_time_query = Some(query);
+        }
+    }
+
+    // Gets the parameters of a draw call for a draw state,
+    // blending with the equation of `GliumGraphics::with_blend` when set.
+    fn draw_parameters(&self, draw_state: &DrawState) -> DrawParameters<'_> {
+        let mut params = self.get_draw_parameters(draw_state);
+        if let Some(blend) = self.blend {
+            params.blend = blend;
+        }
+        params.time_elapsed_query = self.time_query.as_ref();
+        params
+    }
+
+    // Returns the indices for drawing pending vertices.
+    fn indices(&self, batch: Batch, vertices: usize) -> IndicesSource<'_> {
+        match batch {
+            Batch::Triangles => NoIndices(PrimitiveType::TrianglesList).into(),
+            Batch::Quads => self.quad_indices.slice(0..vertices / 4 * 6).unwrap().into(),
+            Batch::Mesh(n) => self.mesh_indices.slice(0..n).unwrap().into(),
+            Batch::Mixed => self
+                .mesh_indices
+                .slice(0..self.batch_indices.len())
+                .unwrap()
+                .into(),
+        }
+    }
+
+    // Uploads the generated indices of a batch mixing quads and triangles.
+    fn write_batch_indices(&mut self, batch: Batch) {
+        if batch == Batch::Mixed {
+            self.mesh_indices
+                .slice(0..self.batch_indices.len())
+                .unwrap()
+                .write(&self.batch_indices);
+        }
+    }
+
+    fn record_flush(&mut self, reason: Flush, vertices: usize) {
+        if let Some(ref mut stats) = self.stats {
+            stats.draw_calls += 1;
+            stats.vertices += vertices;
+            match reason {
+                Flush::State => stats.state_flushes += 1,
+                Flush::Overflow => stats.overflow_flushes += 1,
+                Flush::End | Flush::Mesh | Flush::Immediate => {}
+            }
+        }
+    }
+
+    // Counts the textures of a draw call that the last textured draw call did not bind.
+    fn record_textures(&mut self, textures: &[Texture]) {
+        if let Some(ref mut stats) = self.stats {
+            let last = &self.stats_textures;
+            stats.texture_switches += textures
+                .iter()
+                .filter(|&texture| !last.iter().any(|t| same_texture(Some(t), texture)))
+                .count();
+            self.stats_textures = textures.to_vec();
+        }
+    }
+
+    /// Records graphics once into a static mesh kept on the GPU.
+    ///
+    /// The closure receives a context with the identity transform,
+    /// so the vertices keep the coordinates they are drawn with,
+    /// and the mesh is positioned when drawn with `GliumGraphics::draw_static_mesh`.
+    /// The mesh keeps its textures alive until it is removed.
+    pub fn record_static_mesh<F>(&mut self, f: F) -> Result<StaticMesh, Glium2dError>
+    where
+        F: FnOnce(graphics::Context, &mut MeshRecorder),
+    {
+        let mut recorder = MeshRecorder::new(self.premultiplied_alpha);
+        f(graphics::Context::new(), &mut recorder);
+        let data = recorder.upload(&self.context)?;
+        self.static_meshes.push(Some(data));
+        Ok(StaticMesh(self.static_meshes.len() - 1))
+    }
+
+    /// Removes a static mesh, releasing its buffers and textures.
+    pub fn remove_static_mesh(&mut self, mesh: StaticMesh) {
+        if let Some(data) = self.static_meshes.get_mut(mesh.0) {
+            *data = None;
         }
     }
 
     /// Renders 2D graphics.
-    pub fn draw<F, U>(&mut self, target: &mut Frame, viewport: Viewport, f: F) -> U
+    ///
+    /// The target can be any glium surface, such as a `Frame`
+    /// or a frame buffer created from a texture.
+    ///
+    /// Panics if drawing fails, see `Glium2d::try_draw` for a fallible version.
+    pub fn draw<S, F, U>(&mut self, target: &mut S, viewport: Viewport, f: F) -> U
+    where
+        S: Surface,
+        F: FnOnce(graphics::Context, &mut GliumGraphics<S>) -> U,
+    {
+        self.try_draw(target, viewport, f)
+            .expect("failed to draw triangle list")
+    }
+
+    /// Renders 2D graphics, returning the first draw error.
+    ///
+    /// When a draw call fails, the rest of the frame is still submitted,
+    /// and the error is returned after the closure has been called.
+    pub fn try_draw<S, F, U>(
+        &mut self,
+        target: &mut S,
+        viewport: Viewport,
+        f: F,
+    ) -> Result<U, Glium2dError>
+    where
+        S: Surface,
+        F: FnOnce(graphics::Context, &mut GliumGraphics<S>) -> U,
+    {
+        let multisampled = self.frame_samples >= 2;
+        let res = self.draw_surface(target, viewport, multisampled, f);
+        self.end_stats();
+        res
+    }
+
+    // Renders 2D graphics to a surface,
+    // which is drawn to directly when it is multisampled.
+    fn draw_surface<S, F, U>(
+        &mut self,
+        target: &mut S,
+        viewport: Viewport,
+        multisampled: bool,
+        f: F,
+    ) -> Result<U, Glium2dError>
     where
-        F: FnOnce(graphics::Context, &mut GliumGraphics<Frame>) -> U,
+        S: Surface,
+        F: FnOnce(graphics::Context, &mut GliumGraphics<S>) -> U,
     {
-        use graphics::Context;
+        self.draw_error = None;
+        self.begin_stats();
+        if let Some(ref mut list) = self.display_list {
+            *list = DisplayList::new();
+        }
+        let msaa_targets = if multisampled {
+            None
+        } else {
+            self.msaa_targets(target.get_dimensions())?
+        };
+        let res = {
+            let msaa = match msaa_targets {
+                Some((ref color, ref depth_stencil)) => {
+                    Some(SimpleFrameBuffer::with_depth_stencil_buffer(
+                        &self.context,
+                        color,
+                        depth_stencil,
+                    )?)
+                }
+                None => None,
+            };
+            let ref mut g = GliumGraphics::new(self, target);
+            g.pending_msaa = msaa;
+            let c = graphics::Context::new_viewport(viewport);
+            let res = f(c, g);
+            g.flush(Flush::End);
+            g.resolve();
+            res
+        };
+        if msaa_targets.is_some() {
+            self.msaa_targets = msaa_targets;
+        }
+        // Do not keep textures alive between frames.
+        self.textured_textures.clear();
+        self.textured_color_texture = None;
+        self.material = None;
+        self.blend = None;
+        match self.draw_error.take() {
+            Some(err) => Err(Glium2dError::Draw(err)),
+            None => Ok(res),
+        }
+    }
 
-        let ref mut g = GliumGraphics::new(self, target);
-        let c = Context::new_viewport(viewport);
-        let res = f(c, g);
-        if g.system.colored_offset > 0 {
-            g.flush_colored();
+    // Takes the multisampled targets for a frame, creating them when the size changed.
+    fn msaa_targets(
+        &mut self,
+        (w, h): (u32, u32),
+    ) -> Result<Option<(SrgbTexture2dMultisample, DepthStencilTexture2dMultisample)>, Glium2dError>
+    {
+        if self.samples < 2 {
+            return Ok(None);
         }
+        match self.msaa_targets.take() {
+            Some(targets) if targets.0.dimensions() == (w, h) => Ok(Some(targets)),
+            _ => {
+                let samples = self.samples as u32;
+                let color = SrgbTexture2dMultisample::empty_with_format(
+                    &self.context,
+                    SrgbFormat::U8U8U8U8,
+                    MipmapsOption::NoMipmap,
+                    w,
+                    h,
+                    samples,
+                )?;
+                let depth_stencil = DepthStencilTexture2dMultisample::empty_with_format(
+                    &self.context,
+                    DepthStencilFormat::I24I8,
+                    MipmapsOption::NoMipmap,
+                    w,
+                    h,
+                    samples,
+                )?;
+                Ok(Some((color, depth_stencil)))
+            }
+        }
+    }
+
+    // Takes the layer and target copy for advanced blending, creating them when the size changed.
+    fn blend_targets(
+        &mut self,
+        (w, h): (u32, u32),
+    ) -> Result<(SrgbTexture2d, SrgbTexture2d, DepthStencilRenderBuffer), Glium2dError> {
+        match self.blend_targets.take() {
+            Some(targets) if targets.0.dimensions() == (w, h) => Ok(targets),
+            _ => {
+                let texture = || {
+                    SrgbTexture2d::empty_with_format(
+                        &self.context,
+                        SrgbFormat::U8U8U8U8,
+                        MipmapsOption::NoMipmap,
+                        w,
+                        h,
+                    )
+                };
+                let depth_stencil =
+                    DepthStencilRenderBuffer::new(&self.context, DepthStencilFormat::I24I8, w, h)?;
+                Ok((texture()?, texture()?, depth_stencil))
+            }
+        }
+    }
+
+    /// Renders 2D graphics into a texture.
+    ///
+    /// The viewport covers the whole texture.
+    /// Graphics are stored with the same orientation as textures loaded from images,
+    /// so the result can be drawn with `graphics::Image` without flipping.
+    pub fn draw_to_texture<W, F, U>(
+        &mut self,
+        window: &W,
+        texture: &Texture,
+        f: F,
+    ) -> Result<U, Glium2dError>
+    where
+        W: Facade,
+        F: FnOnce(graphics::Context, &mut GliumGraphics<SimpleFrameBuffer>) -> U,
+    {
+        let (w, h) = texture.get_size();
+        let viewport = Viewport {
+            rect: [0, 0, w as i32, h as i32],
+            draw_size: [w, h],
+            window_size: [w as f64, h as f64],
+        };
+        let depth_stencil = self.texture_depth_stencil((w, h))?;
+        let res = {
+            let mut target = texture_framebuffer(window, texture, &depth_stencil)?;
+            self.draw_to_framebuffer(&mut target, viewport, f)
+        };
+        self.texture_depth_stencil = Some(depth_stencil);
         res
     }
+
+    // Takes the depth stencil buffer for rendering into a texture,
+    // creating it when the size changed.
+    fn texture_depth_stencil(
+        &mut self,
+        (w, h): (u32, u32),
+    ) -> Result<DepthStencilRenderBuffer, Glium2dError> {
+        match self.texture_depth_stencil.take() {
+            Some(depth_stencil) if depth_stencil.get_dimensions() == (w, h) => Ok(depth_stencil),
+            _ => Ok(DepthStencilRenderBuffer::new(
+                &self.context,
+                DepthStencilFormat::I24I8,
+                w,
+                h,
+            )?),
+        }
+    }
+
+    // Renders 2D graphics into an offscreen frame buffer with a custom viewport.
+    pub(crate) fn draw_to_framebuffer<F, U>(
+        &mut self,
+        target: &mut SimpleFrameBuffer,
+        viewport: Viewport,
+        f: F,
+    ) -> Result<U, Glium2dError>
+    where
+        F: FnOnce(graphics::Context, &mut GliumGraphics<SimpleFrameBuffer>) -> U,
+    {
+        self.draw_surface(target, viewport, false, f)
+    }
+
+    /// Renders 2D graphics offscreen and reads back the result.
+    ///
+    /// Works with any facade, including a `Headless` context,
+    /// which makes it possible to compare rendered images in tests.
+    #[cfg(feature = "image")]
+    pub fn draw_to_image<W, F>(
+        &mut self,
+        window: &mut W,
+        width: u32,
+        height: u32,
+        f: F,
+    ) -> Result<RgbaImage, Glium2dError>
+    where
+        W: Facade,
+        F: FnOnce(graphics::Context, &mut GliumGraphics<SimpleFrameBuffer>),
+    {
+        let texture = Texture::empty_with_size(window, width, height, &TextureSettings::new())?;
+        self.draw_to_texture(&*window, &texture, f)?;
+        Ok(texture.read_image())
+    }
+}
+
+// Builds a program for a shading language version.
+//
+// The sources are ordered as GLSL 1.20, GLSL 1.50 core,
+// and the WebGL variants without version directive used for GLSL ES 1.00 and 3.00.
+fn program<W>(
+    window: &W,
+    version: ShaderVersion,
+    color_space: ColorSpace,
+    vertex: [&'static [u8]; 4],
+    fragment: [&'static [u8]; 4],
+) -> Result<Program, Glium2dError>
+where
+    W: Facade,
+{
+    let source = |sources: [&'static [u8]; 4]| -> Result<String, Glium2dError> {
+        let src = |bytes| unsafe { ::std::str::from_utf8_unchecked(bytes) };
+        match version {
+            ShaderVersion::Glsl(glsl) => {
+                let mut shaders = Shaders::new();
+                shaders
+                    .set(GLSL::V1_20, src(sources[0]))
+                    .set(GLSL::V1_50, src(sources[1]));
+                shaders
+                    .get(glsl)
+                    .map(|source| source.to_owned())
+                    .ok_or(Glium2dError::UnsupportedGlsl(glsl))
+            }
+            ShaderVersion::Es100 => Ok(format!("#version 100\n{}", src(sources[2]))),
+            ShaderVersion::Es300 => Ok(format!("#version 300 es\n{}", src(sources[3]))),
+        }
+    };
+    let mut fragment = source(fragment)?;
+    if color_space == ColorSpace::Gamma {
+        fragment = glsl::encode_srgb(&fragment, version);
+    }
+    // Gamma space output is written as is, instead of being encoded by the framebuffer.
+    let input = ProgramCreationInput::SourceCode {
+        vertex_shader: &source(vertex)?,
+        tessellation_control_shader: None,
+        tessellation_evaluation_shader: None,
+        geometry_shader: None,
+        fragment_shader: &fragment,
+        transform_feedback_varyings: None,
+        outputs_srgb: color_space == ColorSpace::Gamma,
+        uses_point_size: false,
+    };
+    Ok(Program::new(window, input)?)
 }
 
 /// Graphics back-end.
 pub struct GliumGraphics<'d, 's, S: 's> {
     system: &'d mut Glium2d,
     surface: &'s mut S,
+    // The multisampled target drawn to instead of the surface.
+    msaa: Option<SimpleFrameBuffer<'s>>,
+    // The multisampled target, used when the frame starts with a clear.
+    pending_msaa: Option<SimpleFrameBuffer<'s>>,
 }
 
 impl<'d, 's, S: Surface> GliumGraphics<'d, 's, S> {
@@ -158,29 +1025,865 @@ impl<'d, 's, S: Surface> GliumGraphics<'d, 's, S> {
         GliumGraphics {
             system: system,
             surface: surface,
+            msaa: None,
+            pending_msaa: None,
+        }
+    }
+
+    fn flush_colored(&mut self, reason: Flush) {
+        if self.system.plain_buffer.pending() == 0 {
+            return;
+        }
+        self.system
+            .record_flush(reason, self.system.plain_buffer.pending());
+        self.system.write_batch_indices(self.system.colored_batch);
+        let slice = self.system.plain_buffer.pending_slice();
+
+        let material = self.system.material.as_ref();
+        let shader = material.map_or(&self.system.shader_color, |m| m.get_program());
+        let indices = self.system.indices(
+            self.system.colored_batch,
+            self.system.plain_buffer.pending(),
+        );
+        let params = self.system.draw_parameters(&self.system.colored_draw_state);
+        let res = with_target!(self, target => target.draw(
+            slice,
+            indices,
+            shader,
+            &WithMaterial {
+                uniforms: uniform! {},
+                material,
+            },
+            &params,
+        ));
+        if self.system.draw_error.is_none() {
+            self.system.draw_error = res.err();
+        }
+
+        self.system.plain_buffer.consume();
+        if let Batch::Mesh(_) | Batch::Mixed = self.system.colored_batch {
+            self.system.colored_batch = Batch::Triangles;
+            self.system.mesh_indices.invalidate();
+        }
+    }
+
+    fn flush_textured(&mut self, reason: Flush) {
+        if self.system.textured_buffer.pending() == 0 {
+            return;
+        }
+        // Vertices are only written after binding their texture.
+        debug_assert!(!self.system.textured_textures.is_empty());
+        if !self.system.textured_textures.is_empty() {
+            self.draw_textured(reason);
+        }
+
+        self.system.textured_buffer.consume();
+        if let Batch::Mesh(_) | Batch::Mixed = self.system.textured_batch {
+            self.system.textured_batch = Batch::Triangles;
+            self.system.mesh_indices.invalidate();
         }
     }
 
-    fn flush_colored(&mut self) {
-        let slice = self
+    fn draw_textured(&mut self, reason: Flush) {
+        self.system
+            .record_flush(reason, self.system.textured_buffer.pending());
+        let textures = mem::take(&mut self.system.textured_textures);
+        self.system.record_textures(&textures);
+        self.system.textured_textures = textures;
+        self.system.write_batch_indices(self.system.textured_batch);
+        let textures = &self.system.textured_textures;
+        // Unused slots repeat the last texture.
+        let slot = |i: usize| &textures[i.min(textures.len() - 1)];
+        let mut alpha = [0.0f32; TEXTURE_SLOTS];
+        for (flag, texture) in alpha.iter_mut().zip(textures) {
+            if texture.is_alpha() {
+                *flag = 1.0;
+            }
+        }
+        let material = self.system.material.as_ref();
+        let shader = material.map_or(&self.system.shader_texture_slots, |m| m.get_program());
+
+        let slice = self.system.textured_buffer.pending_slice();
+
+        let indices = self.system.indices(
+            self.system.textured_batch,
+            self.system.textured_buffer.pending(),
+        );
+        let params = self
             .system
-            .plain_buffer
-            .slice(0..self.system.colored_offset)
-            .unwrap();
-
-        self.surface
-            .draw(
-                slice,
-                &NoIndices(PrimitiveType::TrianglesList),
-                &self.system.shader_color,
-                &uniform! {},
-                &draw_state::convert_draw_state(&self.system.colored_draw_state),
-            )
-            .ok()
-            .expect("failed to draw triangle list");
-
-        self.system.colored_offset = 0;
-        self.system.plain_buffer.invalidate();
+            .draw_parameters(&self.system.textured_draw_state);
+        let res = with_target!(self, target => target.draw(
+            slice,
+            indices,
+            shader,
+            &WithMaterial {
+                uniforms: uniform! {
+                    color: self.system.textured_color,
+                    s_texture: slot(0),
+                    s_texture_0: slot(0),
+                    s_texture_1: slot(1),
+                    s_texture_2: slot(2),
+                    s_texture_3: slot(3),
+                    alpha: alpha,
+                    premultiplied: self.system.premultiplied_alpha
+                },
+                material,
+            },
+            &params,
+        ));
+        if self.system.draw_error.is_none() {
+            self.system.draw_error = res.err();
+        }
+    }
+
+    fn flush_textured_color(&mut self, reason: Flush) {
+        if self.system.textured_color_buffer.pending() == 0 {
+            return;
+        }
+        // Vertices are only written after binding their texture.
+        debug_assert!(self.system.textured_color_texture.is_some());
+        if self.system.textured_color_texture.is_some() {
+            self.draw_textured_color(reason);
+        }
+
+        self.system.textured_color_buffer.consume();
+        if let Batch::Mesh(_) | Batch::Mixed = self.system.textured_color_batch {
+            self.system.textured_color_batch = Batch::Triangles;
+            self.system.mesh_indices.invalidate();
+        }
+    }
+
+    fn draw_textured_color(&mut self, reason: Flush) {
+        self.system
+            .record_flush(reason, self.system.textured_color_buffer.pending());
+        self.system
+            .write_batch_indices(self.system.textured_color_batch);
+        if let Some(texture) = self.system.textured_color_texture.clone() {
+            self.system.record_textures(slice::from_ref(&texture));
+        }
+        let texture = match self.system.textured_color_texture {
+            Some(ref texture) => texture,
+            None => return,
+        };
+        let material = self.system.material.as_ref();
+        let shader = match material {
+            Some(material) => material.get_program(),
+            None if texture.is_alpha() => &self.system.shader_texture_color_alpha,
+            None => &self.system.shader_texture_color,
+        };
+
+        let slice = self.system.textured_color_buffer.pending_slice();
+
+        let indices = self.system.indices(
+            self.system.textured_color_batch,
+            self.system.textured_color_buffer.pending(),
+        );
+        let params = self
+            .system
+            .draw_parameters(&self.system.textured_color_draw_state);
+        let res = with_target!(self, target => target.draw(
+            slice,
+            indices,
+            shader,
+            &WithMaterial {
+                uniforms: uniform! {
+                    s_texture: texture,
+                    premultiplied: self.system.premultiplied_alpha
+                },
+                material,
+            },
+            &params,
+        ));
+        if self.system.draw_error.is_none() {
+            self.system.draw_error = res.err();
+        }
+    }
+
+    /// Draws with a material instead of the built-in shaders within a scope.
+    ///
+    /// Geometry inside the scope is batched as usual,
+    /// and pending geometry is drawn when entering and leaving the scope.
+    /// Scopes can be nested, the innermost material is used.
+    pub fn with_material<F, U>(&mut self, material: &Material, f: F) -> U
+    where
+        F: FnOnce(&mut Self) -> U,
+    {
+        self.flush(Flush::State);
+        let prev = self.system.material.replace(material.clone());
+        let res = f(self);
+        self.flush(Flush::State);
+        self.system.material = prev;
+        res
+    }
+
+    /// Blends with an equation instead of the blend mode of the draw state within a scope.
+    ///
+    /// The equation applies to all geometry inside the scope,
+    /// also when its draw state has no blending.
+    /// Pending geometry is drawn when entering and leaving the scope.
+    /// Scopes can be nested, the innermost equation is used.
+    ///
+    /// Some equations need colors multiplied with their alpha,
+    /// see `BlendEquation::Screen`, and turn on premultiplied alpha within the scope.
+    pub fn with_blend<F, U>(&mut self, equation: BlendEquation, f: F) -> U
+    where
+        F: FnOnce(&mut Self) -> U,
+    {
+        self.flush(Flush::State);
+        let premultiplied = self.system.premultiplied_alpha;
+        let scope_premultiplied = premultiplied || equation.needs_premultiplied();
+        if scope_premultiplied != premultiplied {
+            self.system.set_premultiplied_alpha(true);
+        }
+        let blend = equation.to_glium(scope_premultiplied);
+        let prev = self.system.blend.replace(blend);
+        let res = f(self);
+        self.flush(Flush::State);
+        self.system.blend = prev;
+        if scope_premultiplied != premultiplied {
+            self.system.set_premultiplied_alpha(false);
+        }
+        res
+    }
+
+    /// Draws into a layer within a scope, then blends the layer with the target.
+    ///
+    /// The layer starts out transparent, and geometry inside the scope
+    /// blends with the layer according to its draw state.
+    /// Afterwards the target is copied, and a shader blends the layer with the copy,
+    /// which costs two full-screen passes.
+    ///
+    /// The layer has its own stencil buffer, cleared when entering the scope,
+    /// and is not multisampled.
+    pub fn with_advanced_blend<F, U>(
+        &mut self,
+        mode: AdvancedBlend,
+        f: F,
+    ) -> Result<U, Glium2dError>
+    where
+        F: FnOnce(&mut GliumGraphics<SimpleFrameBuffer>) -> U,
+    {
+        self.flush(Flush::State);
+        let (w, h) = with_target!(self, target => target.get_dimensions());
+        let (layer, dest, depth_stencil) = self.system.blend_targets((w, h))?;
+        let res = {
+            let mut layer_target = SimpleFrameBuffer::with_depth_stencil_buffer(
+                &self.system.context,
+                &layer,
+                &depth_stencil,
+            )?;
+            layer_target.clear_color(0.0, 0.0, 0.0, 0.0);
+            layer_target.clear_stencil(0);
+            let mut g = GliumGraphics::new(self.system, &mut layer_target);
+            let res = f(&mut g);
+            g.flush(Flush::End);
+            res
+        };
+
+        // Copy the target, since a shader cannot read the target it draws to.
+        {
+            let dest_target = SimpleFrameBuffer::new(&self.system.context, &dest)?;
+            let rect = BlitTarget {
+                left: 0,
+                bottom: 0,
+                width: w as i32,
+                height: h as i32,
+            };
+            with_target!(self, target => target.blit_whole_color_to(
+                &dest_target,
+                &rect,
+                MagnifySamplerFilter::Nearest,
+            ));
+        }
+
+        self.system
+            .record_flush(Flush::Immediate, QUAD_CORNERS.len());
+        let params = DrawParameters {
+            time_elapsed_query: self.system.time_query.as_ref(),
+            ..Default::default()
+        };
+        let nearest = |texture| {
+            Sampler::new(texture)
+                .magnify_filter(MagnifySamplerFilter::Nearest)
+                .minify_filter(MinifySamplerFilter::Nearest)
+        };
+        let draw = with_target!(self, target => target.draw(
+            &self.system.sprite_corners,
+            NoIndices(PrimitiveType::TriangleStrip),
+            &self.system.shader_blend,
+            &uniform! {
+                s_layer: nearest(&layer),
+                s_dest: nearest(&dest),
+                mode: mode.to_uniform(),
+                premultiplied: self.system.premultiplied_alpha
+            },
+            &params,
+        ));
+        if self.system.draw_error.is_none() {
+            self.system.draw_error = draw.err();
+        }
+        self.system.blend_targets = Some((layer, dest, depth_stencil));
+        Ok(res)
+    }
+
+    /// Draws a mesh of colored triangles with indices.
+    ///
+    /// Every three indices form a triangle, and each index must be less than
+    /// the number of vertices, so shared vertices are uploaded only once.
+    /// Meshes that do not fit in the vertex buffer are drawn as a triangle list.
+    ///
+    /// Returns an error without drawing when the indices are invalid.
+    pub fn tri_list_indexed(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        vertices: &[[f32; 2]],
+        indices: &[u32],
+    ) -> Result<(), Glium2dError> {
+        check_indices(indices, vertices.len())?;
+        let size = CHUNKS * graphics::BACK_END_MAX_VERTEX_COUNT;
+        if vertices.len() > size || indices.len() > size {
+            self.tri_list(draw_state, color, |f| {
+                for chunk in indices.chunks(graphics::BACK_END_MAX_VERTEX_COUNT / 3 * 3) {
+                    f(&chunk
+                        .iter()
+                        .map(|&i| vertices[i as usize])
+                        .collect::<Vec<_>>())
+                }
+            });
+            return Ok(());
+        }
+
+        self.flush(Flush::State);
+        let color = linear_color(*color, self.system.premultiplied_alpha);
+        self.system.colored_draw_state = *draw_state;
+        let stream = &mut self.system.plain_buffer;
+        stream.staging.clear();
+        stream
+            .staging
+            .extend(vertices.iter().map(|&pos| PlainVertex { color, pos }));
+        stream.write_staging();
+        self.system
+            .mesh_indices
+            .slice(0..indices.len())
+            .unwrap()
+            .write(indices);
+        self.system.colored_batch = Batch::Mesh(indices.len());
+        self.flush_colored(Flush::Mesh);
+        Ok(())
+    }
+
+    /// Draws a mesh of textured triangles with indices.
+    ///
+    /// Works like `GliumGraphics::tri_list_indexed`,
+    /// with a texture coordinate per vertex.
+    pub fn tri_list_uv_indexed(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Texture,
+        vertices: &[[f32; 2]],
+        texture_coords: &[[f32; 2]],
+        indices: &[u32],
+    ) -> Result<(), Glium2dError> {
+        use std::cmp::min;
+
+        let len = min(vertices.len(), texture_coords.len());
+        check_indices(indices, len)?;
+        let size = CHUNKS * graphics::BACK_END_MAX_VERTEX_COUNT;
+        if len > size || indices.len() > size {
+            self.tri_list_uv(draw_state, color, texture, |f| {
+                for chunk in indices.chunks(graphics::BACK_END_MAX_VERTEX_COUNT / 3 * 3) {
+                    f(
+                        &chunk
+                            .iter()
+                            .map(|&i| vertices[i as usize])
+                            .collect::<Vec<_>>(),
+                        &chunk
+                            .iter()
+                            .map(|&i| texture_coords[i as usize])
+                            .collect::<Vec<_>>(),
+                    )
+                }
+            });
+            return Ok(());
+        }
+
+        self.flush(Flush::State);
+        let color = linear_color(*color, self.system.premultiplied_alpha);
+        self.system.textured_textures.clear();
+        self.system.textured_textures.push(texture.clone());
+        self.system.textured_draw_state = *draw_state;
+        self.system.textured_color = color;
+        let stream = &mut self.system.textured_buffer;
+        stream.staging.clear();
+        stream.staging.extend((0..len).map(|i| SlotVertex {
+            pos: vertices[i],
+            // FIXME: The `1.0 - ...` is because of a wrong convention
+            uv: [texture_coords[i][0], 1.0 - texture_coords[i][1]],
+            color,
+            slot: 0.0,
+        }));
+        stream.write_staging();
+        self.system
+            .mesh_indices
+            .slice(0..indices.len())
+            .unwrap()
+            .write(indices);
+        self.system.textured_batch = Batch::Mesh(indices.len());
+        self.flush_textured(Flush::Mesh);
+        Ok(())
+    }
+
+    /// Draws a batch of sprites.
+    ///
+    /// Pending geometry is drawn first, then each texture of the batch
+    /// is drawn with one instanced draw call using the draw state.
+    /// Sprites use the built-in shaders, also within `GliumGraphics::with_material`.
+    /// When the context does not support instancing,
+    /// the sprites are drawn as textured triangles instead.
+    pub fn draw_sprites(&mut self, draw_state: &DrawState, batch: &SpriteBatch) {
+        self.flush(Flush::State);
+        if !self.system.instancing {
+            for group in batch.groups() {
+                self.tri_list_uv_c(draw_state, &group.texture, |f| {
+                    for chunk in group
+                        .sprites
+                        .chunks(graphics::BACK_END_MAX_VERTEX_COUNT / 6)
+                    {
+                        let corners = chunk.iter().flat_map(|sprite| {
+                            QUAD_INDICES
+                                .iter()
+                                .map(move |&i| (sprite, QUAD_CORNERS[i as usize]))
+                        });
+                        let mut vertices = Vec::with_capacity(6 * chunk.len());
+                        let mut texture_coords = Vec::with_capacity(6 * chunk.len());
+                        let mut colors = Vec::with_capacity(6 * chunk.len());
+                        let (tw, th) = group.texture.get_size();
+                        for (sprite, [cx, cy]) in corners {
+                            let [x, y, w, h] = sprite.src_rect;
+                            let (dx, dy) = (cx as f64 * w, cy as f64 * h);
+                            let pos = transform_pos(sprite.transform, [dx, dy]);
+                            vertices.push([pos[0] as f32, pos[1] as f32]);
+                            texture_coords.push([
+                                ((x + dx) / tw as f64) as f32,
+                                ((y + dy) / th as f64) as f32,
+                            ]);
+                            colors.push(sprite.color);
+                        }
+                        f(&vertices, &texture_coords, &colors);
+                    }
+                });
+            }
+            return self.flush(Flush::State);
+        }
+
+        let premultiplied = self.system.premultiplied_alpha;
+        for group in batch.groups() {
+            let texture = &group.texture;
+            let (tw, th) = texture.get_size();
+            for chunk in group.sprites.chunks(SPRITES) {
+                let stream = &mut self.system.sprite_buffer;
+                stream.staging.clear();
+                stream.staging.extend(
+                    chunk.iter().map(|sprite| {
+                        sprite_instance(sprite, [tw as f64, th as f64], premultiplied)
+                    }),
+                );
+                stream.write_staging();
+                self.system
+                    .record_flush(Flush::Immediate, 4 * self.system.sprite_buffer.pending());
+                self.system.record_textures(slice::from_ref(texture));
+
+                let instances = self.system.sprite_buffer.pending_slice();
+                let shader = if texture.is_alpha() {
+                    &self.system.shader_sprite_alpha
+                } else {
+                    &self.system.shader_sprite
+                };
+                let params = self.system.draw_parameters(draw_state);
+                let res = with_target!(self, target => target.draw(
+                    (&self.system.sprite_corners, instances.per_instance().unwrap()),
+                    NoIndices(PrimitiveType::TriangleStrip),
+                    shader,
+                    &uniform! {
+                        s_texture: texture,
+                        premultiplied: self.system.premultiplied_alpha
+                    },
+                    &params,
+                ));
+                if self.system.draw_error.is_none() {
+                    self.system.draw_error = res.err();
+                }
+
+                self.system.sprite_buffer.consume();
+            }
+        }
+    }
+
+    /// Draws a tilemap with a transform, such as `c.transform`.
+    ///
+    /// Pending geometry is drawn first, then the tiles within the camera rectangle,
+    /// which is the part of the tilemap visible through the transform,
+    /// are drawn with one draw call tinted by the color.
+    /// The tilemap uses the built-in shaders, also within `GliumGraphics::with_material`.
+    pub fn draw_tilemap(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        tilemap: &mut Tilemap,
+        transform: Matrix2d,
+    ) {
+        self.flush(Flush::State);
+        let n = tilemap.prepare(transform);
+        if n == 0 {
+            return;
+        }
+        self.system.record_flush(Flush::Immediate, n / 6 * 4);
+
+        let texture = tilemap.get_texture();
+        self.system.record_textures(slice::from_ref(texture));
+        let shader = if texture.is_alpha() {
+            &self.system.shader_transformed_texture_alpha
+        } else {
+            &self.system.shader_transformed_texture
+        };
+        let [transform_x, transform_y] = transform_rows(transform);
+        let params = self.system.draw_parameters(draw_state);
+        let res = with_target!(self, target => target.draw(
+            tilemap.vertices(),
+            tilemap.indices(n),
+            shader,
+            &uniform! {
+                transform_x: transform_x,
+                transform_y: transform_y,
+                color: linear_color(*color, self.system.premultiplied_alpha),
+                s_texture: texture,
+                premultiplied: self.system.premultiplied_alpha
+            },
+            &params,
+        ));
+        if self.system.draw_error.is_none() {
+            self.system.draw_error = res.err();
+        }
+    }
+
+    /// Draws a static mesh with a transform, such as `c.transform`, and a tint.
+    ///
+    /// Pending geometry is drawn first, then each part of the mesh
+    /// with its draw state, and its colors multiplied by the tint.
+    /// The mesh uses the built-in shaders, also within `GliumGraphics::with_material`.
+    /// Does nothing for a removed mesh.
+    pub fn draw_static_mesh(&mut self, mesh: StaticMesh, transform: Matrix2d, tint: &[f32; 4]) {
+        self.flush(Flush::State);
+        let data = match self.system.static_meshes.get_mut(mesh.0) {
+            Some(data) => match data.take() {
+                Some(data) => data,
+                None => return,
+            },
+            None => return,
+        };
+        let [transform_x, transform_y] = transform_rows(transform);
+        let tint = linear_color(*tint, self.system.premultiplied_alpha);
+        for part in &data.parts {
+            let res = match *part {
+                MeshPart::ClearColor(color) => {
+                    self.clear_linear(color);
+                    Ok(())
+                }
+                MeshPart::ClearStencil(value) => {
+                    with_target!(self, target => target.clear_stencil(value as i32));
+                    Ok(())
+                }
+                MeshPart::Colored {
+                    ref draw_state,
+                    ref vertices,
+                } => {
+                    self.system.record_flush(Flush::Immediate, vertices.len());
+                    let slice = data.plain.as_ref().unwrap().slice(vertices.clone());
+                    let params = self.system.draw_parameters(draw_state);
+                    with_target!(self, target => target.draw(
+                        slice.unwrap(),
+                        NoIndices(PrimitiveType::TrianglesList),
+                        &self.system.shader_transformed_color,
+                        &uniform! {
+                            transform_x: transform_x,
+                            transform_y: transform_y,
+                            tint: tint
+                        },
+                        &params,
+                    ))
+                }
+                MeshPart::Textured {
+                    ref draw_state,
+                    color,
+                    ref texture,
+                    ref vertices,
+                } => {
+                    self.system.record_flush(Flush::Immediate, vertices.len());
+                    self.system.record_textures(slice::from_ref(texture));
+                    let slice = data.textured.as_ref().unwrap().slice(vertices.clone());
+                    let shader = if texture.is_alpha() {
+                        &self.system.shader_transformed_texture_alpha
+                    } else {
+                        &self.system.shader_transformed_texture
+                    };
+                    let params = self.system.draw_parameters(draw_state);
+                    with_target!(self, target => target.draw(
+                        slice.unwrap(),
+                        NoIndices(PrimitiveType::TrianglesList),
+                        shader,
+                        &uniform! {
+                            transform_x: transform_x,
+                            transform_y: transform_y,
+                            color: [
+                                color[0] * tint[0],
+                                color[1] * tint[1],
+                                color[2] * tint[2],
+                                color[3] * tint[3],
+                            ],
+                            s_texture: texture,
+                            premultiplied: self.system.premultiplied_alpha
+                        },
+                        &params,
+                    ))
+                }
+                MeshPart::TexturedColor {
+                    ref draw_state,
+                    ref texture,
+                    ref vertices,
+                } => {
+                    self.system.record_flush(Flush::Immediate, vertices.len());
+                    self.system.record_textures(slice::from_ref(texture));
+                    let slice = data
+                        .textured_color
+                        .as_ref()
+                        .unwrap()
+                        .slice(vertices.clone());
+                    let shader = if texture.is_alpha() {
+                        &self.system.shader_transformed_texture_color_alpha
+                    } else {
+                        &self.system.shader_transformed_texture_color
+                    };
+                    let params = self.system.draw_parameters(draw_state);
+                    with_target!(self, target => target.draw(
+                        slice.unwrap(),
+                        NoIndices(PrimitiveType::TrianglesList),
+                        shader,
+                        &uniform! {
+                            transform_x: transform_x,
+                            transform_y: transform_y,
+                            tint: tint,
+                            s_texture: texture,
+                            premultiplied: self.system.premultiplied_alpha
+                        },
+                        &params,
+                    ))
+                }
+            };
+            if self.system.draw_error.is_none() {
+                self.system.draw_error = res.err();
+            }
+        }
+        self.system.static_meshes[mesh.0] = Some(data);
+    }
+
+    // Clears the target with a linear color, encoded for the color space.
+    fn clear_linear(&mut self, color: [f32; 4]) {
+        match self.system.color_space {
+            ColorSpace::Linear => {
+                let (r, g, b, a) = (color[0], color[1], color[2], color[3]);
+                with_target!(self, target => target.clear_color(r, g, b, a));
+            }
+            ColorSpace::Gamma => {
+                let color = gamma_linear_to_srgb(color);
+                let (r, g, b, a) = (color[0], color[1], color[2], color[3]);
+                with_target!(self, target => target.clear_color_srgb(r, g, b, a));
+            }
+        }
+    }
+
+    // Copies the multisampled target into the surface.
+    fn resolve(&mut self) {
+        if let Some(ref msaa) = self.msaa {
+            let (w, h) = msaa.get_dimensions();
+            let target = BlitTarget {
+                left: 0,
+                bottom: 0,
+                width: w as i32,
+                height: h as i32,
+            };
+            msaa.blit_whole_color_to(&*self.surface, &target, MagnifySamplerFilter::Nearest);
+        }
+    }
+
+    /// Draws all pending geometry.
+    fn flush(&mut self, reason: Flush) {
+        self.flush_colored(reason);
+        self.flush_textured(reason);
+        self.flush_textured_color(reason);
+    }
+}
+
+// The reason for drawing pending geometry.
+#[derive(Copy, Clone)]
+enum Flush {
+    // The draw state, texture, color, material or kind of geometry changed.
+    State,
+    // The vertex buffer is full.
+    Overflow,
+    // The frame ended or the target is cleared.
+    End,
+    // An indexed mesh is drawn right away.
+    Mesh,
+    // Sprites, a tilemap or a static mesh are drawn right away.
+    Immediate,
+}
+
+// How pending vertices form triangles.
+#[derive(Copy, Clone, PartialEq)]
+enum Batch {
+    // Every three vertices form a triangle.
+    Triangles,
+    // Every four vertices form a quad, drawn with the shared quad indices.
+    Quads,
+    // The vertices are drawn with a number of mesh indices.
+    Mesh(usize),
+    // Quads and triangles are drawn with the generated batch indices.
+    Mixed,
+}
+
+// Returns `true` if every six vertices are two triangles sharing an edge,
+// which is how rectangles, images and glyphs are triangulated.
+fn is_quads<F>(len: usize, same: F) -> bool
+where
+    F: Fn(usize, usize) -> bool,
+{
+    let quads = len / 6;
+    quads > 0
+        && quads * 6 == len
+        && (0..quads).all(|q| same(6 * q + 3, 6 * q + 1) && same(6 * q + 5, 6 * q + 2))
+}
+
+// Returns the indices of the vertices to upload,
+// skipping the two shared vertices of each quad.
+fn unique_vertices(len: usize, quads: bool) -> impl Iterator<Item = usize> {
+    (0..len).filter(move |i| !quads || (i % 6 != 3 && i % 6 != 5))
+}
+
+// Converts a sprite to the attributes of an instance,
+// for a texture of a size in pixels.
+fn sprite_instance(sprite: &Sprite, [tw, th]: [f64; 2], premultiplied: bool) -> SpriteInstance {
+    let m = sprite.transform;
+    let [x, y, w, h] = sprite.src_rect;
+    SpriteInstance {
+        transform_x: [(m[0][0] * w) as f32, (m[0][1] * h) as f32, m[0][2] as f32],
+        transform_y: [(m[1][0] * w) as f32, (m[1][1] * h) as f32, m[1][2] as f32],
+        // FIXME: The `1.0 - ...` is because of a wrong convention
+        src_uv: [
+            (x / tw) as f32,
+            (1.0 - y / th) as f32,
+            (w / tw) as f32,
+            (-h / th) as f32,
+        ],
+        color: linear_color(sprite.color, premultiplied),
+    }
+}
+
+// Converts a color to linear space, and multiplies it with its alpha when premultiplied.
+pub(crate) fn linear_color(color: [f32; 4], premultiplied: bool) -> [f32; 4] {
+    let [r, g, b, a] = gamma_srgb_to_linear(color);
+    if premultiplied {
+        [r * a, g * a, b * a, a]
+    } else {
+        [r, g, b, a]
+    }
+}
+
+// Returns the rows of a transform, for the shaders transforming vertices.
+fn transform_rows(m: Matrix2d) -> [[f32; 3]; 2] {
+    [
+        [m[0][0] as f32, m[0][1] as f32, m[0][2] as f32],
+        [m[1][0] as f32, m[1][1] as f32, m[1][2] as f32],
+    ]
+}
+
+// Returns an error unless the indices form triangles of a number of vertices.
+fn check_indices(indices: &[u32], vertices: usize) -> Result<(), Glium2dError> {
+    if indices.len() / 3 * 3 == indices.len() && indices.iter().all(|&i| (i as usize) < vertices) {
+        Ok(())
+    } else {
+        Err(Glium2dError::InvalidIndices)
+    }
+}
+
+// Returns the kind of batch for triangles.
+fn batch_kind(quads: bool) -> Batch {
+    if quads {
+        Batch::Quads
+    } else {
+        Batch::Triangles
+    }
+}
+
+// Adds a number of uploaded vertices to a batch, after the `start` pending vertices.
+//
+// Quads and triangles are drawn in the same batch
+// by generating indices once both kinds are mixed.
+fn extend_batch(batch: &mut Batch, indices: &mut Vec<u32>, start: usize, len: usize, quads: bool) {
+    if len == 0 {
+        return;
+    }
+    let kind = batch_kind(quads);
+    if start == 0 {
+        *batch = kind;
+        indices.clear();
+        return;
+    }
+    if *batch == kind {
+        return;
+    }
+    if *batch != Batch::Mixed {
+        indices.clear();
+        push_indices(indices, 0, start, *batch == Batch::Quads);
+        *batch = Batch::Mixed;
+    }
+    push_indices(indices, start, len, quads);
+}
+
+// Appends the indices of quads or triangles starting at a vertex.
+fn push_indices(indices: &mut Vec<u32>, start: usize, len: usize, quads: bool) {
+    let start = start as u32;
+    if quads {
+        indices.extend(
+            (0..len as u32 / 4).flat_map(|q| QUAD_INDICES.iter().map(move |i| start + 4 * q + i)),
+        );
+    } else {
+        indices.extend(start..start + len as u32);
+    }
+}
+
+// Creates a frame buffer that renders into a texture.
+pub(crate) fn texture_framebuffer<'a, W: Facade>(
+    window: &W,
+    texture: &'a Texture,
+    depth_stencil: &'a DepthStencilRenderBuffer,
+) -> Result<SimpleFrameBuffer<'a>, Glium2dError> {
+    Ok(match *texture.storage() {
+        TextureStorage::Srgb(ref tex) => {
+            SimpleFrameBuffer::with_depth_stencil_buffer(window, tex, depth_stencil)?
+        }
+        TextureStorage::Linear(ref tex) | TextureStorage::Alpha(ref tex) => {
+            SimpleFrameBuffer::with_depth_stencil_buffer(window, tex, depth_stencil)?
+        }
+    })
+}
+
+// Returns `true` if textured geometry can be batched into the same draw call.
+pub(crate) fn same_texture(a: Option<&Texture>, b: &Texture) -> bool {
+    match a {
+        Some(a) => a.same_sampling(b),
+        None => false,
     }
 }
 
@@ -190,13 +1893,23 @@ impl<'d, 's, S: Surface> Graphics for GliumGraphics<'d, 's, S> {
 
     /// Clears background with a color.
     fn clear_color(&mut self, color: [f32; 4]) {
-        let color = gamma_srgb_to_linear(color);
-        let (r, g, b, a) = (color[0], color[1], color[2], color[3]);
-        self.surface.clear_color(r, g, b, a);
+        if let Some(ref mut list) = self.system.display_list {
+            list.push(Command::ClearColor(color));
+        }
+        self.flush(Flush::End);
+        // Nothing is drawn yet, so the multisampled target can replace the surface.
+        if let Some(msaa) = self.pending_msaa.take() {
+            self.msaa = Some(msaa);
+        }
+        self.clear_linear(linear_color(color, self.system.premultiplied_alpha));
     }
 
     fn clear_stencil(&mut self, value: u8) {
-        self.surface.clear_stencil(value as i32);
+        if let Some(ref mut list) = self.system.display_list {
+            list.push(Command::ClearStencil(value));
+        }
+        self.flush(Flush::End);
+        with_target!(self, target => target.clear_stencil(value as i32));
     }
 
     /// Renders list of 2d triangles.
@@ -204,31 +1917,50 @@ impl<'d, 's, S: Surface> Graphics for GliumGraphics<'d, 's, S> {
     where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
     {
-        let color = gamma_srgb_to_linear(*color);
+        if let Some(ref mut list) = self.system.display_list {
+            list.push(Command::TriList {
+                draw_state: *draw_state,
+                color: *color,
+                vertices: vec![],
+            });
+        }
+        let color = linear_color(*color, self.system.premultiplied_alpha);
+        if self.system.textured_buffer.pending() > 0 {
+            self.flush_textured(Flush::State);
+        }
+        if self.system.textured_color_buffer.pending() > 0 {
+            self.flush_textured_color(Flush::State);
+        }
         // Flush when draw state changes.
         if &self.system.colored_draw_state != draw_state {
-            self.flush_colored();
+            self.flush_colored(Flush::State);
             self.system.colored_draw_state = *draw_state;
         }
         f(&mut |vertices: &[[f32; 2]]| {
-            let n = vertices.len();
-            if self.system.colored_offset + n > CHUNKS * graphics::BACK_END_MAX_VERTEX_COUNT {
-                self.flush_colored();
+            if let Some(ref mut list) = self.system.display_list {
+                list.extend(vertices, &[], &[]);
             }
-            let slice = self
-                .system
-                .plain_buffer
-                .slice(self.system.colored_offset..self.system.colored_offset + n)
-                .unwrap();
-            slice.write({
-                &(0..n)
-                    .map(|i| PlainVertex {
-                        color: color,
-                        pos: vertices[i],
-                    })
-                    .collect::<Vec<_>>()
-            });
-            self.system.colored_offset += n;
+            let quads = is_quads(vertices.len(), |a, b| vertices[a] == vertices[b]);
+            let stream = &mut self.system.plain_buffer;
+            stream.staging.clear();
+            stream
+                .staging
+                .extend(unique_vertices(vertices.len(), quads).map(|i| PlainVertex {
+                    color,
+                    pos: vertices[i],
+                }));
+            if !stream.has_room() {
+                self.flush_colored(Flush::Overflow);
+            }
+            let system = &mut *self.system;
+            extend_batch(
+                &mut system.colored_batch,
+                &mut system.batch_indices,
+                system.plain_buffer.pending(),
+                system.plain_buffer.staging.len(),
+                quads,
+            );
+            system.plain_buffer.write_staging();
         })
     }
 
@@ -236,30 +1968,52 @@ impl<'d, 's, S: Surface> Graphics for GliumGraphics<'d, 's, S> {
     where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
     {
+        if let Some(ref mut list) = self.system.display_list {
+            list.push(Command::TriListC {
+                draw_state: *draw_state,
+                vertices: vec![],
+                colors: vec![],
+            });
+        }
+        if self.system.textured_buffer.pending() > 0 {
+            self.flush_textured(Flush::State);
+        }
+        if self.system.textured_color_buffer.pending() > 0 {
+            self.flush_textured_color(Flush::State);
+        }
         // Flush when draw state changes.
         if &self.system.colored_draw_state != draw_state {
-            self.flush_colored();
+            self.flush_colored(Flush::State);
             self.system.colored_draw_state = *draw_state;
         }
+        let premultiplied = self.system.premultiplied_alpha;
         f(&mut |vertices: &[[f32; 2]], colors: &[[f32; 4]]| {
-            let n = vertices.len();
-            if self.system.colored_offset + n > CHUNKS * graphics::BACK_END_MAX_VERTEX_COUNT {
-                self.flush_colored();
+            if let Some(ref mut list) = self.system.display_list {
+                list.extend(vertices, &[], colors);
             }
-            let slice = self
-                .system
-                .plain_buffer
-                .slice(self.system.colored_offset..self.system.colored_offset + n)
-                .unwrap();
-            slice.write({
-                &(0..n)
-                    .map(|i| PlainVertex {
-                        color: gamma_srgb_to_linear(colors[i]),
-                        pos: vertices[i],
-                    })
-                    .collect::<Vec<_>>()
+            let quads = is_quads(vertices.len(), |a, b| {
+                vertices[a] == vertices[b] && colors[a] == colors[b]
             });
-            self.system.colored_offset += n;
+            let stream = &mut self.system.plain_buffer;
+            stream.staging.clear();
+            stream
+                .staging
+                .extend(unique_vertices(vertices.len(), quads).map(|i| PlainVertex {
+                    color: linear_color(colors[i], premultiplied),
+                    pos: vertices[i],
+                }));
+            if !stream.has_room() {
+                self.flush_colored(Flush::Overflow);
+            }
+            let system = &mut *self.system;
+            extend_batch(
+                &mut system.colored_batch,
+                &mut system.batch_indices,
+                system.plain_buffer.pending(),
+                system.plain_buffer.staging.len(),
+                quads,
+            );
+            system.plain_buffer.write_staging();
         })
     }
 
@@ -267,6 +2021,8 @@ impl<'d, 's, S: Surface> Graphics for GliumGraphics<'d, 's, S> {
     ///
     /// A texture coordinate is assigned per vertex.
     /// The texture coordinates refers to the current texture.
+    /// Several textures are bound at once and each vertex selects one,
+    /// so triangles switching between a few textures are drawn together.
     fn tri_list_uv<F>(
         &mut self,
         draw_state: &DrawState,
@@ -276,94 +2032,151 @@ impl<'d, 's, S: Surface> Graphics for GliumGraphics<'d, 's, S> {
     ) where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
     {
-        use glium::uniforms::{Sampler, SamplerWrapFunction};
         use std::cmp::min;
 
-        let mut sampler = Sampler::new(&texture.0);
-        sampler.1.wrap_function = (texture.1[0], texture.1[1], SamplerWrapFunction::Clamp);
-
-        let color = gamma_srgb_to_linear(*color);
-        if self.system.colored_offset > 0 {
-            self.flush_colored();
+        if let Some(ref mut list) = self.system.display_list {
+            let texture = list.texture_index(texture);
+            list.push(Command::TriListUv {
+                draw_state: *draw_state,
+                color: *color,
+                texture,
+                vertices: vec![],
+                texture_coords: vec![],
+            });
+        }
+        let color = linear_color(*color, self.system.premultiplied_alpha);
+        if self.system.plain_buffer.pending() > 0 {
+            self.flush_colored(Flush::State);
+        }
+        if self.system.textured_color_buffer.pending() > 0 {
+            self.flush_textured_color(Flush::State);
         }
+        // Flush when draw state changes, or color changes with a material,
+        // which gets the color and texture as uniforms.
+        let single = self.system.material.is_some();
+        if &self.system.textured_draw_state != draw_state
+            || (single && self.system.textured_color != color)
+        {
+            self.flush_textured(Flush::State);
+            self.system.textured_draw_state = *draw_state;
+            self.system.textured_color = color;
+        }
+        // Bind the texture to a free slot, and flush when all slots are taken.
+        // With a material, only the first slot is used.
+        let slots = if single { 1 } else { TEXTURE_SLOTS };
+        let textures = &self.system.textured_textures;
+        let slot = match textures.iter().position(|t| same_texture(Some(t), texture)) {
+            Some(slot) if slot < slots => slot,
+            _ => {
+                if textures.len() >= slots {
+                    self.flush_textured(Flush::State);
+                    self.system.textured_textures.clear();
+                }
+                self.system.textured_textures.push(texture.clone());
+                self.system.textured_textures.len() - 1
+            }
+        };
+        let slot = slot as f32;
         f(&mut |vertices: &[[f32; 2]], texture_coords: &[[f32; 2]]| {
+            if let Some(ref mut list) = self.system.display_list {
+                list.extend(vertices, texture_coords, &[]);
+            }
             let len = min(vertices.len(), texture_coords.len());
-
-            self.system.textured_buffer.invalidate();
-            let slice = self.system.textured_buffer.slice(0..len).unwrap();
-
-            slice.write({
-                &(0..len)
-                    .map(|i| TexturedVertex {
-                        pos: vertices[i],
-                        // FIXME: The `1.0 - ...` is because of a wrong convention
-                        uv: [texture_coords[i][0], 1.0 - texture_coords[i][1]],
-                    })
-                    .collect::<Vec<_>>()
+            let quads = is_quads(len, |a, b| {
+                vertices[a] == vertices[b] && texture_coords[a] == texture_coords[b]
             });
-
-            self.surface
-                .draw(
-                    slice,
-                    &NoIndices(PrimitiveType::TrianglesList),
-                    &self.system.shader_texture,
-                    &uniform! {
-                        color: color,
-                        s_texture: sampler
-                    },
-                    &draw_state::convert_draw_state(draw_state),
-                )
-                .ok()
-                .expect("failed to draw triangle list");
+            let stream = &mut self.system.textured_buffer;
+            stream.staging.clear();
+            stream
+                .staging
+                .extend(unique_vertices(len, quads).map(|i| SlotVertex {
+                    pos: vertices[i],
+                    // FIXME: The `1.0 - ...` is because of a wrong convention
+                    uv: [texture_coords[i][0], 1.0 - texture_coords[i][1]],
+                    color,
+                    slot,
+                }));
+            if !stream.has_room() {
+                self.flush_textured(Flush::Overflow);
+            }
+            let system = &mut *self.system;
+            extend_batch(
+                &mut system.textured_batch,
+                &mut system.batch_indices,
+                system.textured_buffer.pending(),
+                system.textured_buffer.staging.len(),
+                quads,
+            );
+            system.textured_buffer.write_staging();
         })
     }
 
-    fn tri_list_uv_c<F>(
-        &mut self,
-        draw_state: &DrawState,
-        texture: &Texture,
-        mut f: F,
-    ) where
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Texture, mut f: F)
+    where
         F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
     {
-        use glium::uniforms::{Sampler, SamplerWrapFunction};
         use std::cmp::min;
 
-        let mut sampler = Sampler::new(&texture.0);
-        sampler.1.wrap_function = (texture.1[0], texture.1[1], SamplerWrapFunction::Clamp);
-
-        if self.system.colored_offset > 0 {
-            self.flush_colored();
+        if let Some(ref mut list) = self.system.display_list {
+            let texture = list.texture_index(texture);
+            list.push(Command::TriListUvC {
+                draw_state: *draw_state,
+                texture,
+                vertices: vec![],
+                texture_coords: vec![],
+                colors: vec![],
+            });
         }
-        f(&mut |vertices: &[[f32; 2]], texture_coords: &[[f32; 2]], colors: &[[f32; 4]]| {
-            let len = min(min(vertices.len(), texture_coords.len()), colors.len());
-
-            self.system.textured_color_buffer.invalidate();
-            let slice = self.system.textured_color_buffer.slice(0..len).unwrap();
-
-            slice.write({
-                &(0..len)
-                    .map(|i| TexturedColorVertex {
+        if self.system.plain_buffer.pending() > 0 {
+            self.flush_colored(Flush::State);
+        }
+        if self.system.textured_buffer.pending() > 0 {
+            self.flush_textured(Flush::State);
+        }
+        // Flush when texture or draw state changes.
+        let texture_changed = !same_texture(self.system.textured_color_texture.as_ref(), texture);
+        if texture_changed || &self.system.textured_color_draw_state != draw_state {
+            if self.system.textured_color_buffer.pending() > 0 {
+                self.flush_textured_color(Flush::State);
+            }
+            self.system.textured_color_texture = Some(texture.clone());
+            self.system.textured_color_draw_state = *draw_state;
+        }
+        let premultiplied = self.system.premultiplied_alpha;
+        f(
+            &mut |vertices: &[[f32; 2]], texture_coords: &[[f32; 2]], colors: &[[f32; 4]]| {
+                if let Some(ref mut list) = self.system.display_list {
+                    list.extend(vertices, texture_coords, colors);
+                }
+                let len = min(min(vertices.len(), texture_coords.len()), colors.len());
+                let quads = is_quads(len, |a, b| {
+                    vertices[a] == vertices[b]
+                        && texture_coords[a] == texture_coords[b]
+                        && colors[a] == colors[b]
+                });
+                let stream = &mut self.system.textured_color_buffer;
+                stream.staging.clear();
+                stream
+                    .staging
+                    .extend(unique_vertices(len, quads).map(|i| TexturedColorVertex {
                         pos: vertices[i],
                         // FIXME: The `1.0 - ...` is because of a wrong convention
                         uv: [texture_coords[i][0], 1.0 - texture_coords[i][1]],
-                        color: gamma_srgb_to_linear(colors[i]),
-                    })
-                    .collect::<Vec<_>>()
-            });
-
-            self.surface
-                .draw(
-                    slice,
-                    &NoIndices(PrimitiveType::TrianglesList),
-                    &self.system.shader_texture_color,
-                    &uniform! {
-                        s_texture: sampler
-                    },
-                    &draw_state::convert_draw_state(draw_state),
-                )
-                .ok()
-                .expect("failed to draw triangle list");
-        })
+                        color: linear_color(colors[i], premultiplied),
+                    }));
+                if !stream.has_room() {
+                    self.flush_textured_color(Flush::Overflow);
+                }
+                let system = &mut *self.system;
+                extend_batch(
+                    &mut system.textured_color_batch,
+                    &mut system.batch_indices,
+                    system.textured_color_buffer.pending(),
+                    system.textured_color_buffer.staging.len(),
+                    quads,
+                );
+                system.textured_color_buffer.write_staging();
+            },
+        )
     }
 }
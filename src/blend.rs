@@ -0,0 +1,109 @@
+use glium::{Blend, BlendingFunction};
+
+/// A fixed-function blend equation, see `GliumGraphics::with_blend`.
+///
+/// Extends the blend modes of `graphics::draw_state::Blend`,
+/// taking premultiplied alpha into account.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlendEquation {
+    /// Brightens the destination, `1 - (1 - src) * (1 - dst)`.
+    ///
+    /// The destination is weighted by the source color,
+    /// so the source must be multiplied with its alpha first.
+    /// Without premultiplied alpha, the scope premultiplies colors and alpha textures,
+    /// but other textures must be opaque or uploaded with premultiplied alpha.
+    Screen,
+    /// Subtracts the source color from the destination.
+    Subtract,
+    /// Keeps the darker of the source and destination colors.
+    ///
+    /// Ignores the source alpha, since the blend factors are not used.
+    Min,
+    /// Keeps the lighter of the source and destination colors.
+    ///
+    /// Ignores the source alpha, since the blend factors are not used.
+    Max,
+    /// Any glium blend equation, used as it is.
+    Custom(Blend),
+}
+
+impl BlendEquation {
+    // Returns `true` if the equation only works for premultiplied colors.
+    pub(crate) fn needs_premultiplied(self) -> bool {
+        self == BlendEquation::Screen
+    }
+
+    // Converts to a glium blend equation.
+    pub(crate) fn to_glium(self, premultiplied: bool) -> Blend {
+        use glium::LinearBlendingFactor::*;
+
+        // Scales the source color by its alpha, unless it is premultiplied already.
+        let source = if premultiplied { One } else { SourceAlpha };
+        let alpha = BlendingFunction::Addition {
+            source: One,
+            destination: OneMinusSourceAlpha,
+        };
+        match self {
+            BlendEquation::Screen => Blend {
+                color: BlendingFunction::Addition {
+                    source,
+                    destination: OneMinusSourceColor,
+                },
+                alpha,
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            BlendEquation::Subtract => Blend {
+                color: BlendingFunction::ReverseSubtraction {
+                    source,
+                    destination: One,
+                },
+                alpha: BlendingFunction::Addition {
+                    source: Zero,
+                    destination: One,
+                },
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            BlendEquation::Min => Blend {
+                color: BlendingFunction::Min,
+                alpha,
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            BlendEquation::Max => Blend {
+                color: BlendingFunction::Max,
+                alpha,
+                constant_value: (0.0, 0.0, 0.0, 0.0),
+            },
+            BlendEquation::Custom(blend) => blend,
+        }
+    }
+}
+
+/// A blend mode computed in a shader, see `GliumGraphics::with_advanced_blend`.
+///
+/// These modes depend on the destination color in ways
+/// fixed-function blending cannot express.
+/// The formulas follow the separable blend modes of the W3C compositing specification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdvancedBlend {
+    /// Multiplies dark and screens light destination colors.
+    Overlay,
+    /// Multiplies dark and screens light source colors.
+    HardLight,
+    /// A softer version of `HardLight`.
+    SoftLight,
+    /// Brightens the destination to reflect the source.
+    ColorDodge,
+    /// Darkens the destination to reflect the source.
+    ColorBurn,
+    /// The absolute difference of the source and destination colors.
+    Difference,
+    /// Like `Difference` with lower contrast.
+    Exclusion,
+}
+
+impl AdvancedBlend {
+    // The mode passed to the blend shader.
+    pub(crate) fn to_uniform(self) -> i32 {
+        self as i32
+    }
+}
@@ -0,0 +1,38 @@
+use glium::backend::Facade;
+use glium::CapabilitiesSource;
+
+/// The color space in which `Glium2d` blends colors.
+///
+/// Colors and textures are in sRGB either way,
+/// and are converted to linear space for the shaders.
+/// The color space decides how the shaders write their output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Blends linear colors, which the framebuffer encodes to sRGB.
+    ///
+    /// This needs an sRGB framebuffer, otherwise colors come out too dark.
+    /// Textures drawn to with `Glium2d::draw_to_texture` are always sRGB.
+    #[default]
+    Linear,
+    /// Blends sRGB encoded colors, like image editors and most older renderers.
+    ///
+    /// The shaders encode their output to sRGB,
+    /// which works with framebuffers that have no sRGB support.
+    /// Materials still write linear colors.
+    Gamma,
+}
+
+impl ColorSpace {
+    /// Picks linear blending when the default framebuffer supports sRGB,
+    /// and gamma space blending otherwise.
+    pub fn detect<W>(window: &W) -> ColorSpace
+    where
+        W: Facade,
+    {
+        if window.get_context().get_capabilities().srgb {
+            ColorSpace::Linear
+        } else {
+            ColorSpace::Gamma
+        }
+    }
+}
@@ -0,0 +1,739 @@
+use back_end::same_texture;
+use graphics::draw_state::{Blend, Stencil};
+use graphics::{self, DrawState, Graphics, ImageSize};
+use std::cmp::min;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+use std::str::SplitWhitespace;
+use Texture;
+
+/// A graphics call recorded in a `DisplayList`.
+///
+/// Vertices are recorded after the transform, and colors in sRGB,
+/// as they are passed to `Graphics`.
+/// Textures are referenced by their index in the display list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Clears the target with a color.
+    ClearColor([f32; 4]),
+    /// Clears the stencil buffer with a value.
+    ClearStencil(u8),
+    /// Triangles with one color.
+    TriList {
+        /// The draw state.
+        draw_state: DrawState,
+        /// The color of all vertices.
+        color: [f32; 4],
+        /// The vertices, every three forming a triangle.
+        vertices: Vec<[f32; 2]>,
+    },
+    /// Triangles with a color per vertex.
+    TriListC {
+        /// The draw state.
+        draw_state: DrawState,
+        /// The vertices, every three forming a triangle.
+        vertices: Vec<[f32; 2]>,
+        /// The colors of the vertices.
+        colors: Vec<[f32; 4]>,
+    },
+    /// Textured triangles with one color.
+    TriListUv {
+        /// The draw state.
+        draw_state: DrawState,
+        /// The color multiplied with the texture.
+        color: [f32; 4],
+        /// The index of the texture.
+        texture: usize,
+        /// The vertices, every three forming a triangle.
+        vertices: Vec<[f32; 2]>,
+        /// The texture coordinates of the vertices.
+        texture_coords: Vec<[f32; 2]>,
+    },
+    /// Textured triangles with a color per vertex.
+    TriListUvC {
+        /// The draw state.
+        draw_state: DrawState,
+        /// The index of the texture.
+        texture: usize,
+        /// The vertices, every three forming a triangle.
+        vertices: Vec<[f32; 2]>,
+        /// The texture coordinates of the vertices.
+        texture_coords: Vec<[f32; 2]>,
+        /// The colors of the vertices.
+        colors: Vec<[f32; 4]>,
+    },
+}
+
+/// The graphics calls of a frame, recorded by `Glium2d`.
+///
+/// Enable recording with `Glium2d::set_record_display_list`.
+/// A display list can be replayed onto any surface with `Glium2d::replay`,
+/// compared with another list, and written to a file for bug reports.
+/// Only calls through `Graphics` are recorded,
+/// not sprites, tilemaps, static meshes or indexed meshes.
+///
+/// The text format has one command per line,
+/// and lists the textures with their size, since they can not be stored.
+/// After reading a list, textures can be attached with `DisplayList::set_texture`,
+/// and textured commands without a texture are skipped when replaying.
+#[derive(Clone, Default)]
+pub struct DisplayList {
+    commands: Vec<Command>,
+    textures: Vec<Option<Texture>>,
+    texture_sizes: Vec<[u32; 2]>,
+}
+
+impl DisplayList {
+    /// Creates a new empty display list.
+    pub fn new() -> DisplayList {
+        DisplayList::default()
+    }
+
+    /// Returns the recorded commands.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Returns the number of textures referenced by the commands.
+    pub fn texture_count(&self) -> usize {
+        self.texture_sizes.len()
+    }
+
+    /// Gets a texture by index,
+    /// or `None` for a texture that was not attached after reading.
+    pub fn get_texture(&self, index: usize) -> Option<&Texture> {
+        self.textures
+            .get(index)
+            .and_then(|texture| texture.as_ref())
+    }
+
+    /// Gets the size of a texture by index.
+    pub fn get_texture_size(&self, index: usize) -> Option<[u32; 2]> {
+        self.texture_sizes.get(index).cloned()
+    }
+
+    /// Attaches a texture by index, for example after reading a list from a file.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set_texture(&mut self, index: usize, texture: Texture) {
+        self.textures[index] = Some(texture);
+    }
+
+    /// Returns the index of the first command that differs from another list,
+    /// or `None` if the lists are the same.
+    ///
+    /// Textures are compared by index and size.
+    pub fn diff(&self, other: &DisplayList) -> Option<usize> {
+        let different = self
+            .commands
+            .iter()
+            .zip(&other.commands)
+            .position(|(a, b)| a != b || self.texture_sizes_differ(other, a));
+        match different {
+            Some(i) => Some(i),
+            None if self.commands.len() != other.commands.len() => {
+                Some(min(self.commands.len(), other.commands.len()))
+            }
+            None => None,
+        }
+    }
+
+    fn texture_sizes_differ(&self, other: &DisplayList, command: &Command) -> bool {
+        match *command {
+            Command::TriListUv { texture, .. } | Command::TriListUvC { texture, .. } => {
+                self.get_texture_size(texture) != other.get_texture_size(texture)
+            }
+            _ => false,
+        }
+    }
+
+    /// Draws the commands with a graphics back-end.
+    pub fn replay<G>(&self, g: &mut G)
+    where
+        G: Graphics<Texture = Texture>,
+    {
+        // Keep chunks of whole quads, so they are batched as when recorded.
+        let chunk = graphics::BACK_END_MAX_VERTEX_COUNT / 6 * 6;
+        for command in &self.commands {
+            match *command {
+                Command::ClearColor(color) => g.clear_color(color),
+                Command::ClearStencil(value) => g.clear_stencil(value),
+                Command::TriList {
+                    ref draw_state,
+                    ref color,
+                    ref vertices,
+                } => g.tri_list(draw_state, color, |f| {
+                    for vertices in vertices.chunks(chunk) {
+                        f(vertices)
+                    }
+                }),
+                Command::TriListC {
+                    ref draw_state,
+                    ref vertices,
+                    ref colors,
+                } => g.tri_list_c(draw_state, |f| {
+                    for (vertices, colors) in vertices.chunks(chunk).zip(colors.chunks(chunk)) {
+                        f(vertices, colors)
+                    }
+                }),
+                Command::TriListUv {
+                    ref draw_state,
+                    ref color,
+                    texture,
+                    ref vertices,
+                    ref texture_coords,
+                } => {
+                    if let Some(texture) = self.get_texture(texture) {
+                        g.tri_list_uv(draw_state, color, texture, |f| {
+                            for (vertices, texture_coords) in
+                                vertices.chunks(chunk).zip(texture_coords.chunks(chunk))
+                            {
+                                f(vertices, texture_coords)
+                            }
+                        })
+                    }
+                }
+                Command::TriListUvC {
+                    ref draw_state,
+                    texture,
+                    ref vertices,
+                    ref texture_coords,
+                    ref colors,
+                } => {
+                    if let Some(texture) = self.get_texture(texture) {
+                        g.tri_list_uv_c(draw_state, texture, |f| {
+                            for ((vertices, texture_coords), colors) in vertices
+                                .chunks(chunk)
+                                .zip(texture_coords.chunks(chunk))
+                                .zip(colors.chunks(chunk))
+                            {
+                                f(vertices, texture_coords, colors)
+                            }
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes the list in the text format.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "display_list")?;
+        for (i, &[width, height]) in self.texture_sizes.iter().enumerate() {
+            writeln!(w, "texture {} {} {}", i, width, height)?;
+        }
+        for command in &self.commands {
+            writeln!(w, "{}", command)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a list in the text format, without textures.
+    pub fn read<R: BufRead>(r: R) -> io::Result<DisplayList> {
+        let mut list = DisplayList::new();
+        let mut lines = r.lines();
+        let header = match lines.next() {
+            Some(line) => line?,
+            None => String::new(),
+        };
+        if header.trim() != "display_list" {
+            return Err(invalid("missing `display_list` header"));
+        }
+        for line in lines {
+            let line = line?;
+            let mut tokens = Tokens(line.split_whitespace());
+            let name = match tokens.0.next() {
+                Some(name) => name,
+                None => continue,
+            };
+            if name == "texture" {
+                let index = tokens.parse::<usize>()?;
+                if index != list.texture_sizes.len() {
+                    return Err(invalid("textures out of order"));
+                }
+                list.texture_sizes
+                    .push([tokens.parse::<u32>()?, tokens.parse::<u32>()?]);
+                list.textures.push(None);
+            } else {
+                let command = tokens.command(name)?;
+                match command {
+                    Command::TriListUv { texture, .. } | Command::TriListUvC { texture, .. }
+                        if texture >= list.texture_sizes.len() =>
+                    {
+                        return Err(invalid("unknown texture"));
+                    }
+                    _ => {}
+                }
+                list.commands.push(command);
+            }
+        }
+        Ok(list)
+    }
+
+    /// Writes the list in the text format to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write(&mut w)?;
+        w.flush()
+    }
+
+    // Starts recording a call.
+    pub(crate) fn push(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    // Returns the index of a texture, adding it when it is new.
+    pub(crate) fn texture_index(&mut self, texture: &Texture) -> usize {
+        let found = self
+            .textures
+            .iter()
+            .position(|t| same_texture(t.as_ref(), texture));
+        match found {
+            Some(i) => i,
+            None => {
+                let (w, h) = texture.get_size();
+                self.textures.push(Some(texture.clone()));
+                self.texture_sizes.push([w, h]);
+                self.textures.len() - 1
+            }
+        }
+    }
+
+    // Adds a chunk of vertices to the last call.
+    pub(crate) fn extend(
+        &mut self,
+        new_vertices: &[[f32; 2]],
+        new_texture_coords: &[[f32; 2]],
+        new_colors: &[[f32; 4]],
+    ) {
+        match self.commands.last_mut() {
+            Some(&mut Command::TriList {
+                ref mut vertices, ..
+            }) => vertices.extend_from_slice(new_vertices),
+            Some(&mut Command::TriListC {
+                ref mut vertices,
+                ref mut colors,
+                ..
+            }) => {
+                let len = min(new_vertices.len(), new_colors.len());
+                vertices.extend_from_slice(&new_vertices[..len]);
+                colors.extend_from_slice(&new_colors[..len]);
+            }
+            Some(&mut Command::TriListUv {
+                ref mut vertices,
+                ref mut texture_coords,
+                ..
+            }) => {
+                let len = min(new_vertices.len(), new_texture_coords.len());
+                vertices.extend_from_slice(&new_vertices[..len]);
+                texture_coords.extend_from_slice(&new_texture_coords[..len]);
+            }
+            Some(&mut Command::TriListUvC {
+                ref mut vertices,
+                ref mut texture_coords,
+                ref mut colors,
+                ..
+            }) => {
+                let len = min(
+                    min(new_vertices.len(), new_texture_coords.len()),
+                    new_colors.len(),
+                );
+                vertices.extend_from_slice(&new_vertices[..len]);
+                texture_coords.extend_from_slice(&new_texture_coords[..len]);
+                colors.extend_from_slice(&new_colors[..len]);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl PartialEq for DisplayList {
+    fn eq(&self, other: &DisplayList) -> bool {
+        self.texture_sizes == other.texture_sizes && self.commands == other.commands
+    }
+}
+
+impl fmt::Debug for DisplayList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DisplayList")
+            .field("commands", &self.commands)
+            .field("texture_sizes", &self.texture_sizes)
+            .finish()
+    }
+}
+
+/// Formats the command as a line of the text format.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::ClearColor(color) => {
+                write!(f, "clear_color")?;
+                write_floats(f, &color)
+            }
+            Command::ClearStencil(value) => write!(f, "clear_stencil {}", value),
+            Command::TriList {
+                ref draw_state,
+                ref color,
+                ref vertices,
+            } => {
+                write!(f, "tri_list")?;
+                write_draw_state(f, draw_state)?;
+                write_floats(f, color)?;
+                write!(f, " {}", vertices.len())?;
+                for v in vertices {
+                    write_floats(f, v)?;
+                }
+                Ok(())
+            }
+            Command::TriListC {
+                ref draw_state,
+                ref vertices,
+                ref colors,
+            } => {
+                write!(f, "tri_list_c")?;
+                write_draw_state(f, draw_state)?;
+                write!(f, " {}", vertices.len())?;
+                for (v, c) in vertices.iter().zip(colors) {
+                    write_floats(f, v)?;
+                    write_floats(f, c)?;
+                }
+                Ok(())
+            }
+            Command::TriListUv {
+                ref draw_state,
+                ref color,
+                texture,
+                ref vertices,
+                ref texture_coords,
+            } => {
+                write!(f, "tri_list_uv")?;
+                write_draw_state(f, draw_state)?;
+                write_floats(f, color)?;
+                write!(f, " {} {}", texture, vertices.len())?;
+                for (v, uv) in vertices.iter().zip(texture_coords) {
+                    write_floats(f, v)?;
+                    write_floats(f, uv)?;
+                }
+                Ok(())
+            }
+            Command::TriListUvC {
+                ref draw_state,
+                texture,
+                ref vertices,
+                ref texture_coords,
+                ref colors,
+            } => {
+                write!(f, "tri_list_uv_c")?;
+                write_draw_state(f, draw_state)?;
+                write!(f, " {} {}", texture, vertices.len())?;
+                for ((v, uv), c) in vertices.iter().zip(texture_coords).zip(colors) {
+                    write_floats(f, v)?;
+                    write_floats(f, uv)?;
+                    write_floats(f, c)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_floats(f: &mut fmt::Formatter, values: &[f32]) -> fmt::Result {
+    for value in values {
+        write!(f, " {:?}", value)?;
+    }
+    Ok(())
+}
+
+// Writes the blend mode, scissor and stencil.
+fn write_draw_state(f: &mut fmt::Formatter, draw_state: &DrawState) -> fmt::Result {
+    let blend = match draw_state.blend {
+        None => "none",
+        Some(Blend::Alpha) => "alpha",
+        Some(Blend::Add) => "add",
+        Some(Blend::Lighter) => "lighter",
+        Some(Blend::Multiply) => "multiply",
+        Some(Blend::Invert) => "invert",
+    };
+    write!(f, " {}", blend)?;
+    match draw_state.scissor {
+        None => write!(f, " none")?,
+        Some([x, y, w, h]) => write!(f, " {},{},{},{}", x, y, w, h)?,
+    }
+    match draw_state.stencil {
+        None => write!(f, " none"),
+        Some(Stencil::Clip(value)) => write!(f, " clip:{}", value),
+        Some(Stencil::Inside(value)) => write!(f, " inside:{}", value),
+        Some(Stencil::Outside(value)) => write!(f, " outside:{}", value),
+        Some(Stencil::Increment) => write!(f, " increment"),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Parses the tokens of a line in the text format.
+struct Tokens<'a>(SplitWhitespace<'a>);
+
+impl<'a> Tokens<'a> {
+    fn word(&mut self) -> io::Result<&'a str> {
+        self.0
+            .next()
+            .ok_or_else(|| invalid("unexpected end of line"))
+    }
+
+    fn parse<T: ::std::str::FromStr>(&mut self) -> io::Result<T> {
+        self.word()?.parse().map_err(|_| invalid("invalid number"))
+    }
+
+    fn vec2(&mut self) -> io::Result<[f32; 2]> {
+        Ok([self.parse()?, self.parse()?])
+    }
+
+    fn vec4(&mut self) -> io::Result<[f32; 4]> {
+        Ok([self.parse()?, self.parse()?, self.parse()?, self.parse()?])
+    }
+
+    fn draw_state(&mut self) -> io::Result<DrawState> {
+        let blend = match self.word()? {
+            "none" => None,
+            "alpha" => Some(Blend::Alpha),
+            "add" => Some(Blend::Add),
+            "lighter" => Some(Blend::Lighter),
+            "multiply" => Some(Blend::Multiply),
+            "invert" => Some(Blend::Invert),
+            _ => return Err(invalid("unknown blend mode")),
+        };
+        let scissor = match self.word()? {
+            "none" => None,
+            rect => {
+                let values = rect
+                    .split(',')
+                    .map(|v| v.parse::<u32>().map_err(|_| invalid("invalid scissor")))
+                    .collect::<io::Result<Vec<_>>>()?;
+                match values[..] {
+                    [x, y, w, h] => Some([x, y, w, h]),
+                    _ => return Err(invalid("invalid scissor")),
+                }
+            }
+        };
+        let stencil = match self.word()? {
+            "none" => None,
+            "increment" => Some(Stencil::Increment),
+            stencil => {
+                let mut parts = stencil.splitn(2, ':');
+                let kind = parts.next().unwrap();
+                let value = parts
+                    .next()
+                    .and_then(|v| v.parse::<u8>().ok())
+                    .ok_or_else(|| invalid("invalid stencil"))?;
+                match kind {
+                    "clip" => Some(Stencil::Clip(value)),
+                    "inside" => Some(Stencil::Inside(value)),
+                    "outside" => Some(Stencil::Outside(value)),
+                    _ => return Err(invalid("invalid stencil")),
+                }
+            }
+        };
+        Ok(DrawState {
+            blend,
+            scissor,
+            stencil,
+        })
+    }
+
+    fn command(&mut self, name: &str) -> io::Result<Command> {
+        let command = match name {
+            "clear_color" => Command::ClearColor(self.vec4()?),
+            "clear_stencil" => Command::ClearStencil(self.parse()?),
+            "tri_list" => {
+                let draw_state = self.draw_state()?;
+                let color = self.vec4()?;
+                let n = self.parse::<usize>()?;
+                Command::TriList {
+                    draw_state,
+                    color,
+                    vertices: (0..n).map(|_| self.vec2()).collect::<io::Result<_>>()?,
+                }
+            }
+            "tri_list_c" => {
+                let draw_state = self.draw_state()?;
+                let n = self.parse::<usize>()?;
+                let (mut vertices, mut colors) = (vec![], vec![]);
+                for _ in 0..n {
+                    vertices.push(self.vec2()?);
+                    colors.push(self.vec4()?);
+                }
+                Command::TriListC {
+                    draw_state,
+                    vertices,
+                    colors,
+                }
+            }
+            "tri_list_uv" => {
+                let draw_state = self.draw_state()?;
+                let color = self.vec4()?;
+                let texture = self.parse()?;
+                let n = self.parse::<usize>()?;
+                let (mut vertices, mut texture_coords) = (vec![], vec![]);
+                for _ in 0..n {
+                    vertices.push(self.vec2()?);
+                    texture_coords.push(self.vec2()?);
+                }
+                Command::TriListUv {
+                    draw_state,
+                    color,
+                    texture,
+                    vertices,
+                    texture_coords,
+                }
+            }
+            "tri_list_uv_c" => {
+                let draw_state = self.draw_state()?;
+                let texture = self.parse()?;
+                let n = self.parse::<usize>()?;
+                let (mut vertices, mut texture_coords, mut colors) = (vec![], vec![], vec![]);
+                for _ in 0..n {
+                    vertices.push(self.vec2()?);
+                    texture_coords.push(self.vec2()?);
+                    colors.push(self.vec4()?);
+                }
+                Command::TriListUvC {
+                    draw_state,
+                    texture,
+                    vertices,
+                    texture_coords,
+                    colors,
+                }
+            }
+            _ => return Err(invalid("unknown command")),
+        };
+        match self.0.next() {
+            Some(_) => Err(invalid("unexpected token")),
+            None => Ok(command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw_state(blend: Option<Blend>, stencil: Option<Stencil>) -> DrawState {
+        DrawState {
+            blend,
+            scissor: Some([1, 2, 30, 40]),
+            stencil,
+        }
+    }
+
+    fn tri_list(color: [f32; 4]) -> Command {
+        Command::TriList {
+            draw_state: DrawState::default(),
+            color,
+            vertices: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+        }
+    }
+
+    fn list(commands: Vec<Command>) -> DisplayList {
+        let mut list = DisplayList::new();
+        for command in commands {
+            list.push(command);
+        }
+        list
+    }
+
+    fn round_trip(list: &DisplayList) -> DisplayList {
+        let mut bytes = vec![];
+        list.write(&mut bytes).unwrap();
+        DisplayList::read(&bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn write_read() {
+        let mut list = list(vec![
+            Command::ClearColor([0.1, 0.2, 0.3, 1.0]),
+            Command::ClearStencil(7),
+            Command::TriList {
+                draw_state: draw_state(None, Some(Stencil::Clip(1))),
+                color: [1.0, 0.5, 0.25, 0.125],
+                vertices: vec![[-0.5, 0.5], [0.75, -1.0], [0.3, 0.7]],
+            },
+            Command::TriListC {
+                draw_state: draw_state(Some(Blend::Add), Some(Stencil::Inside(2))),
+                vertices: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+                colors: vec![[1.0, 0.0, 0.0, 1.0]; 3],
+            },
+            Command::TriListUv {
+                draw_state: draw_state(Some(Blend::Lighter), Some(Stencil::Outside(255))),
+                color: [1.0; 4],
+                texture: 0,
+                vertices: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+                texture_coords: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+            },
+            Command::TriListUvC {
+                draw_state: draw_state(Some(Blend::Multiply), Some(Stencil::Increment)),
+                texture: 1,
+                vertices: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+                texture_coords: vec![[0.0, 1.0], [1.0, 1.0], [0.0, 0.0]],
+                colors: vec![[0.0, 0.0, 1.0, 0.5]; 3],
+            },
+            Command::TriList {
+                draw_state: DrawState {
+                    blend: Some(Blend::Invert),
+                    ..DrawState::default()
+                },
+                color: [0.0; 4],
+                vertices: vec![],
+            },
+            tri_list([1.0; 4]),
+        ]);
+        for &size in &[[16, 8], [1, 1]] {
+            list.texture_sizes.push(size);
+            list.textures.push(None);
+        }
+        let read = round_trip(&list);
+        assert_eq!(read, list);
+        assert_eq!(read.get_texture_size(1), Some([1, 1]));
+        assert_eq!(list.diff(&read), None);
+    }
+
+    #[test]
+    fn read_errors() {
+        assert!(DisplayList::read(&b"tri_list"[..]).is_err());
+        assert!(DisplayList::read(&b"display_list\nclear_stencil 1 2"[..]).is_err());
+        assert!(DisplayList::read(&b"display_list\nclear_color 1 2 3"[..]).is_err());
+        assert!(
+            DisplayList::read(&b"display_list\ntri_list_uv alpha none none 1 1 1 1 0 0"[..])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn diff_first_different() {
+        let a = list(vec![
+            tri_list([1.0; 4]),
+            tri_list([0.5; 4]),
+            tri_list([1.0; 4]),
+        ]);
+        let b = list(vec![
+            tri_list([1.0; 4]),
+            tri_list([0.5; 4]),
+            tri_list([0.0; 4]),
+        ]);
+        assert_eq!(a.diff(&a), None);
+        assert_eq!(a.diff(&b), Some(2));
+        assert_eq!(b.diff(&a), Some(2));
+    }
+
+    #[test]
+    fn diff_shorter() {
+        let a = list(vec![tri_list([1.0; 4]), tri_list([0.5; 4])]);
+        let b = list(vec![tri_list([1.0; 4])]);
+        assert_eq!(a.diff(&b), Some(1));
+        assert_eq!(b.diff(&a), Some(1));
+        assert_eq!(DisplayList::new().diff(&b), Some(0));
+    }
+}
@@ -3,9 +3,15 @@ use graphics::draw_state;
 
 pub fn convert_draw_state(
     draw_state: &draw_state::DrawState,
+    premultiplied: bool,
 ) -> glium::draw_parameters::DrawParameters<'static> {
+    let blend = if premultiplied {
+        convert_blend_premultiplied(draw_state.blend)
+    } else {
+        convert_blend(draw_state.blend)
+    };
     glium::draw_parameters::DrawParameters {
-        blend: convert_blend(draw_state.blend),
+        blend,
         scissor: convert_scissor(draw_state.scissor),
         stencil: convert_stencil(draw_state.stencil),
         // polygon_mode: use default (Fill)
@@ -143,3 +149,60 @@ pub fn convert_blend(blend: Option<draw_state::Blend>) -> glium::Blend {
         }
     }
 }
+
+// Converts blend modes for colors with premultiplied alpha.
+pub fn convert_blend_premultiplied(blend: Option<draw_state::Blend>) -> glium::Blend {
+    use glium::{BlendingFunction, LinearBlendingFactor};
+    use graphics::draw_state::Blend;
+
+    match blend {
+        // The source color is already multiplied with its alpha.
+        Some(Blend::Alpha) => glium::Blend {
+            color: BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            },
+            alpha: BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            },
+            constant_value: (0.0, 0.0, 0.0, 0.0),
+        },
+        Some(Blend::Lighter) => glium::Blend {
+            color: BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::One,
+            },
+            alpha: BlendingFunction::Addition {
+                source: LinearBlendingFactor::Zero,
+                destination: LinearBlendingFactor::One,
+            },
+            constant_value: (0.0, 0.0, 0.0, 0.0),
+        },
+        // Adds the color weighted by alpha, since it is premultiplied.
+        Some(Blend::Add) => glium::Blend {
+            color: BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::One,
+            },
+            alpha: BlendingFunction::Addition {
+                source: LinearBlendingFactor::One,
+                destination: LinearBlendingFactor::One,
+            },
+            constant_value: (0.0, 0.0, 0.0, 0.0),
+        },
+        // Multiplies where the source is opaque, and keeps the destination where it is transparent.
+        Some(Blend::Multiply) => glium::Blend {
+            color: BlendingFunction::Addition {
+                source: LinearBlendingFactor::DestinationColor,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            },
+            alpha: BlendingFunction::Addition {
+                source: LinearBlendingFactor::DestinationAlpha,
+                destination: LinearBlendingFactor::OneMinusSourceAlpha,
+            },
+            constant_value: (0.0, 0.0, 0.0, 0.0),
+        },
+        blend => convert_blend(blend),
+    }
+}
@@ -0,0 +1,129 @@
+use glium::framebuffer::{RenderBufferCreationError, ValidationError};
+use glium::index::BufferCreationError as IndexBufferCreationError;
+use glium::texture::TextureCreationError;
+use glium::vertex::BufferCreationError;
+use glium::{DrawError, ProgramCreationError};
+use shader_version::glsl::GLSL;
+use std::error::Error;
+use std::fmt;
+
+/// An error that can happen when creating or rendering with `Glium2d`.
+#[derive(Debug)]
+pub enum Glium2dError {
+    /// There are no shaders for the GLSL version.
+    UnsupportedGlsl(GLSL),
+    /// The context supports none of the shading languages with shaders.
+    NoSupportedShaders,
+    /// A shader failed to compile or link.
+    Program(ProgramCreationError),
+    /// A vertex buffer could not be allocated.
+    Buffer(BufferCreationError),
+    /// An index buffer could not be allocated.
+    IndexBuffer(IndexBufferCreationError),
+    /// A texture could not be created.
+    Texture(TextureCreationError),
+    /// A render buffer could not be created.
+    RenderBuffer(RenderBufferCreationError),
+    /// A frame buffer could not be created.
+    FrameBuffer(ValidationError),
+    /// Drawing failed, for example because the context was lost.
+    Draw(DrawError),
+    /// An image with padding does not fit in an atlas page.
+    TooLargeForAtlas([u32; 2]),
+    /// Mesh indices do not form triangles, or refer to missing vertices.
+    InvalidIndices,
+    /// The number of tiles does not match the columns and rows of a tilemap.
+    TileCount(usize, [usize; 2]),
+}
+
+impl fmt::Display for Glium2dError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Glium2dError::UnsupportedGlsl(glsl) => write!(f, "No shaders for {:?}", glsl),
+            Glium2dError::NoSupportedShaders => write!(f, "No shaders for the context"),
+            Glium2dError::Program(ref err) => write!(f, "Failed to create program: {}", err),
+            Glium2dError::Buffer(ref err) => write!(f, "Failed to create buffer: {}", err),
+            Glium2dError::IndexBuffer(ref err) => {
+                write!(f, "Failed to create index buffer: {}", err)
+            }
+            Glium2dError::Texture(ref err) => write!(f, "Failed to create texture: {}", err),
+            Glium2dError::RenderBuffer(ref err) => {
+                write!(f, "Failed to create render buffer: {}", err)
+            }
+            Glium2dError::FrameBuffer(ref err) => {
+                write!(f, "Failed to create frame buffer: {}", err)
+            }
+            Glium2dError::Draw(ref err) => write!(f, "Failed to draw: {}", err),
+            Glium2dError::TooLargeForAtlas([w, h]) => {
+                write!(f, "Image of size {}x{} does not fit in atlas", w, h)
+            }
+            Glium2dError::InvalidIndices => write!(f, "Invalid mesh indices"),
+            Glium2dError::TileCount(n, [columns, rows]) => write!(
+                f,
+                "{} tiles do not fill a tilemap of {}x{}",
+                n, columns, rows
+            ),
+        }
+    }
+}
+
+impl Error for Glium2dError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Glium2dError::UnsupportedGlsl(_)
+            | Glium2dError::NoSupportedShaders
+            | Glium2dError::TooLargeForAtlas(_)
+            | Glium2dError::InvalidIndices
+            | Glium2dError::TileCount(..) => None,
+            Glium2dError::Program(ref err) => Some(err),
+            Glium2dError::Buffer(ref err) => Some(err),
+            Glium2dError::IndexBuffer(ref err) => Some(err),
+            Glium2dError::Texture(ref err) => Some(err),
+            Glium2dError::RenderBuffer(ref err) => Some(err),
+            Glium2dError::FrameBuffer(ref err) => Some(err),
+            Glium2dError::Draw(ref err) => Some(err),
+        }
+    }
+}
+
+impl From<ProgramCreationError> for Glium2dError {
+    fn from(err: ProgramCreationError) -> Glium2dError {
+        Glium2dError::Program(err)
+    }
+}
+
+impl From<BufferCreationError> for Glium2dError {
+    fn from(err: BufferCreationError) -> Glium2dError {
+        Glium2dError::Buffer(err)
+    }
+}
+
+impl From<IndexBufferCreationError> for Glium2dError {
+    fn from(err: IndexBufferCreationError) -> Glium2dError {
+        Glium2dError::IndexBuffer(err)
+    }
+}
+
+impl From<TextureCreationError> for Glium2dError {
+    fn from(err: TextureCreationError) -> Glium2dError {
+        Glium2dError::Texture(err)
+    }
+}
+
+impl From<RenderBufferCreationError> for Glium2dError {
+    fn from(err: RenderBufferCreationError) -> Glium2dError {
+        Glium2dError::RenderBuffer(err)
+    }
+}
+
+impl From<ValidationError> for Glium2dError {
+    fn from(err: ValidationError) -> Glium2dError {
+        Glium2dError::FrameBuffer(err)
+    }
+}
+
+impl From<DrawError> for Glium2dError {
+    fn from(err: DrawError) -> Glium2dError {
+        Glium2dError::Draw(err)
+    }
+}
@@ -1,20 +1,33 @@
 #[cfg(feature = "image")]
-extern crate image;
-
+use image;
+use std::borrow::Cow;
+#[cfg(feature = "image")]
+use std::io::{BufRead, Cursor, Seek};
 #[cfg(feature = "image")]
 use std::path::Path;
+use std::rc::Rc;
 
-#[cfg(feature = "image")]
-use self::image::{DynamicImage, RgbaImage};
 use glium::backend::Facade;
+use glium::framebuffer::SimpleFrameBuffer;
 use glium::texture::srgb_texture2d::SrgbTexture2d;
-use glium::texture::{RawImage2d, TextureCreationError};
-use glium::uniforms::SamplerWrapFunction;
+use glium::texture::texture2d::Texture2d;
+use glium::texture::{
+    ClientFormat, MipmapsOption, RawImage2d, TextureCreationError, UncompressedFloatFormat,
+};
+use glium::uniforms::{
+    AsUniformValue, MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior,
+    SamplerWrapFunction, UniformValue,
+};
+use glium::{BlitTarget, Surface};
+use graphics::color::{gamma_linear_to_srgb, gamma_srgb_to_linear};
 use graphics::ImageSize;
+#[cfg(feature = "image")]
+use image::{DynamicImage, ImageReader, RgbaImage};
 use texture::{self, CreateTexture, Format, TextureOp, TextureSettings, UpdateTexture};
+use Glium2dError;
 
 /// Flip settings.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Flip {
     /// Does not flip.
     None,
@@ -22,13 +35,100 @@ pub enum Flip {
     Vertical,
 }
 
+/// The GPU storage of a texture.
+pub enum TextureStorage {
+    /// sRGB encoded colors, converted to linear space when sampled.
+    Srgb(SrgbTexture2d),
+    /// Linear colors, sampled as stored.
+    ///
+    /// Use this for data such as lookup tables, heightmaps and masks.
+    Linear(Texture2d),
+    /// Single channel alpha, stored in the red channel.
+    ///
+    /// The shaders use the stored value as alpha with white color.
+    Alpha(Texture2d),
+}
+
+// Selects the kind of storage when creating a texture.
+#[derive(Clone, Copy)]
+enum StorageKind {
+    Srgb,
+    Linear,
+    Alpha,
+}
+
 /// Wrapper for 2D texture.
-pub struct Texture(pub SrgbTexture2d, pub [SamplerWrapFunction; 2]);
+///
+/// Stores the wrap functions for u and v,
+/// and the minifying and magnifying filters used when sampling.
+///
+/// Cloning is cheap and shares the GPU texture.
+/// The fields are private, use `Texture::storage` or `Texture::as_srgb`
+/// to access the GPU texture.
+#[derive(Clone)]
+pub struct Texture(
+    Rc<TextureStorage>,
+    [SamplerWrapFunction; 2],
+    (MinifySamplerFilter, MagnifySamplerFilter),
+);
 
 impl Texture {
     /// Creates a new `Texture`.
     pub fn new(texture: SrgbTexture2d) -> Texture {
-        Texture(texture, [SamplerWrapFunction::Clamp; 2])
+        Texture::with_storage(TextureStorage::Srgb(texture))
+    }
+
+    /// Creates a new `Texture` from storage.
+    pub fn with_storage(storage: TextureStorage) -> Texture {
+        Texture(
+            Rc::new(storage),
+            [SamplerWrapFunction::Clamp; 2],
+            (MinifySamplerFilter::Linear, MagnifySamplerFilter::Linear),
+        )
+    }
+
+    /// Returns the GPU storage of the texture.
+    pub fn storage(&self) -> &TextureStorage {
+        &self.0
+    }
+
+    /// Returns the sRGB texture, or `None` for linear and alpha storage.
+    pub fn as_srgb(&self) -> Option<&SrgbTexture2d> {
+        match *self.0 {
+            TextureStorage::Srgb(ref tex) => Some(tex),
+            TextureStorage::Linear(_) | TextureStorage::Alpha(_) => None,
+        }
+    }
+
+    /// Returns `true` if the texture stores a single alpha channel.
+    pub fn is_alpha(&self) -> bool {
+        matches!(*self.0, TextureStorage::Alpha(_))
+    }
+
+    // Returns `true` if both share the GPU texture and sample it the same way.
+    pub(crate) fn same_sampling(&self, other: &Texture) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) && self.1 == other.1 && self.2 == other.2
+    }
+
+    /// Returns the sampler behavior from wrap functions and filters.
+    pub(crate) fn sampler_behavior(&self) -> SamplerBehavior {
+        SamplerBehavior {
+            wrap_function: (self.1[0], self.1[1], SamplerWrapFunction::Clamp),
+            minify_filter: (self.2).0,
+            magnify_filter: (self.2).1,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the uniform value for sampling the texture.
+    pub(crate) fn uniform_value(&self) -> UniformValue<'_> {
+        let behavior = Some(self.sampler_behavior());
+        match *self.0 {
+            TextureStorage::Srgb(ref tex) => UniformValue::SrgbTexture2d(tex, behavior),
+            TextureStorage::Linear(ref tex) | TextureStorage::Alpha(ref tex) => {
+                UniformValue::Texture2d(tex, behavior)
+            }
+        }
     }
 
     /// Returns empty texture.
@@ -45,6 +145,61 @@ impl Texture {
         )
     }
 
+    /// Returns empty texture with a size, cleared to transparent black.
+    ///
+    /// This is useful as a target for `Glium2d::draw_to_texture`.
+    pub fn empty_with_size<F>(
+        factory: &mut F,
+        width: u32,
+        height: u32,
+        settings: &TextureSettings,
+    ) -> Result<Self, TextureCreationError>
+    where
+        F: Facade,
+    {
+        if width == 0 || height == 0 {
+            return Texture::empty(factory);
+        }
+
+        let buffer = vec![0u8; width as usize * height as usize * 4];
+        CreateTexture::create(factory, Format::Rgba8, &buffer, [width, height], settings)
+    }
+
+    /// Creates a texture by copying the colors of a surface,
+    /// such as the frame being drawn.
+    ///
+    /// Call `read_image` on the texture to get the pixels,
+    /// or `save` to take a screenshot.
+    pub fn from_surface<F, S>(
+        factory: &mut F,
+        surface: &S,
+        settings: &TextureSettings,
+    ) -> Result<Self, Glium2dError>
+    where
+        F: Facade,
+        S: Surface,
+    {
+        let (width, height) = surface.get_dimensions();
+        let texture = Texture::empty_with_size(factory, width, height, settings)?;
+        {
+            let target = match *texture.0 {
+                TextureStorage::Srgb(ref tex) => SimpleFrameBuffer::new(&*factory, tex)?,
+                TextureStorage::Linear(ref tex) | TextureStorage::Alpha(ref tex) => {
+                    SimpleFrameBuffer::new(&*factory, tex)?
+                }
+            };
+            let rect = BlitTarget {
+                left: 0,
+                bottom: 0,
+                width: width as i32,
+                height: height as i32,
+            };
+            // Both keep the bottom row first, so the rows are copied in order.
+            surface.blit_whole_color_to(&target, &rect, MagnifySamplerFilter::Nearest);
+        }
+        Ok(texture)
+    }
+
     /// Creates a texture from path.
     #[cfg(feature = "image")]
     pub fn from_path<F, P>(
@@ -57,19 +212,43 @@ impl Texture {
         F: Facade,
         P: AsRef<Path>,
     {
-        let img = image::open(path).map_err(|e| e.to_string())?;
-
-        let img = match img {
-            DynamicImage::ImageRgba8(img) => img,
-            img => img.to_rgba8(),
-        };
+        let img = open(path, flip)?;
+        Texture::from_image(factory, &img, settings).map_err(|e| format!("{:?}", e))
+    }
 
-        let img = if flip == Flip::Vertical {
-            image::imageops::flip_vertical(&img)
-        } else {
-            img
-        };
+    /// Creates a texture from encoded image data in memory,
+    /// such as an asset embedded with `include_bytes!`.
+    ///
+    /// Supports the formats of the `image` crate, detected from the data.
+    #[cfg(feature = "image")]
+    pub fn from_bytes<F>(
+        factory: &mut F,
+        bytes: &[u8],
+        flip: Flip,
+        settings: &TextureSettings,
+    ) -> Result<Self, String>
+    where
+        F: Facade,
+    {
+        Texture::from_reader(factory, Cursor::new(bytes), flip, settings)
+    }
 
+    /// Creates a texture from a reader of encoded image data,
+    /// such as a file in an archive.
+    ///
+    /// Supports the formats of the `image` crate, detected from the data.
+    #[cfg(feature = "image")]
+    pub fn from_reader<F, R>(
+        factory: &mut F,
+        reader: R,
+        flip: Flip,
+        settings: &TextureSettings,
+    ) -> Result<Self, String>
+    where
+        F: Facade,
+        R: BufRead + Seek,
+    {
+        let img = decode(reader, flip)?;
         Texture::from_image(factory, &img, settings).map_err(|e| format!("{:?}", e))
     }
 
@@ -87,7 +266,85 @@ impl Texture {
         CreateTexture::create(factory, Format::Rgba8, img, [width, height], settings)
     }
 
+    /// Creates a texture from image, multiplying the colors with their alpha.
+    ///
+    /// This is for drawing with `Glium2d::set_premultiplied_alpha`.
+    #[cfg(feature = "image")]
+    pub fn from_image_premultiplied<F>(
+        factory: &mut F,
+        img: &RgbaImage,
+        settings: &TextureSettings,
+    ) -> Result<Self, TextureCreationError>
+    where
+        F: Facade,
+    {
+        let (width, height) = img.dimensions();
+        Texture::from_memory_premultiplied(factory, img, width, height, settings)
+    }
+
+    /// Creates a texture from image, without gamma conversion.
+    #[cfg(feature = "image")]
+    pub fn from_image_linear<F>(
+        factory: &mut F,
+        img: &RgbaImage,
+        settings: &TextureSettings,
+    ) -> Result<Self, TextureCreationError>
+    where
+        F: Facade,
+    {
+        let (width, height) = img.dimensions();
+        Texture::create_storage(factory, StorageKind::Linear, img, [width, height], settings)
+    }
+
+    /// Creates texture from memory RGBA, multiplying the colors with their alpha.
+    ///
+    /// The colors are multiplied in linear space and stored as sRGB.
+    /// This is for drawing with `Glium2d::set_premultiplied_alpha`.
+    pub fn from_memory_premultiplied<F>(
+        factory: &mut F,
+        buffer: &[u8],
+        width: u32,
+        height: u32,
+        settings: &TextureSettings,
+    ) -> Result<Self, TextureCreationError>
+    where
+        F: Facade,
+    {
+        if width == 0 || height == 0 {
+            return Texture::empty(factory);
+        }
+
+        let buffer = premultiply(buffer);
+        CreateTexture::create(factory, Format::Rgba8, &buffer, [width, height], settings)
+    }
+
+    /// Creates texture from memory RGBA, without gamma conversion.
+    pub fn from_memory_linear<F>(
+        factory: &mut F,
+        buffer: &[u8],
+        width: u32,
+        height: u32,
+        settings: &TextureSettings,
+    ) -> Result<Self, TextureCreationError>
+    where
+        F: Facade,
+    {
+        if width == 0 || height == 0 {
+            return Texture::empty(factory);
+        }
+
+        Texture::create_storage(
+            factory,
+            StorageKind::Linear,
+            buffer,
+            [width, height],
+            settings,
+        )
+    }
+
     /// Creates texture from memory alpha.
+    ///
+    /// The alpha values are stored in a single channel texture.
     pub fn from_memory_alpha<F>(
         factory: &mut F,
         buffer: &[u8],
@@ -102,9 +359,13 @@ impl Texture {
             return Texture::empty(factory);
         }
 
-        let size = [width, height];
-        let buffer = texture::ops::alpha_to_rgba8(buffer, size);
-        CreateTexture::create(factory, Format::Rgba8, &buffer, size, settings)
+        Texture::create_storage(
+            factory,
+            StorageKind::Alpha,
+            buffer,
+            [width, height],
+            settings,
+        )
     }
 
     /// Updates texture with an image.
@@ -120,12 +381,176 @@ impl Texture {
         let (width, height) = img.dimensions();
         UpdateTexture::update(self, factory, Format::Rgba8, img, [0, 0], [width, height])
     }
+
+    /// Reads the texture back into an image, with the first row at the top.
+    ///
+    /// Textures drawn to with `Glium2d::draw_to_texture`
+    /// or copied with `from_surface` read the way they looked on screen.
+    /// Alpha textures are read as white with alpha.
+    #[cfg(feature = "image")]
+    pub fn read_image(&self) -> RgbaImage {
+        let raw: RawImage2d<u8> = match *self.0 {
+            TextureStorage::Srgb(ref tex) => tex.read(),
+            TextureStorage::Linear(ref tex) => tex.read(),
+            TextureStorage::Alpha(ref tex) => {
+                let raw: RawImage2d<u8> = tex.read();
+                let mut data = Vec::with_capacity(raw.data.len());
+                for p in raw.data.chunks(4) {
+                    data.extend_from_slice(&[255, 255, 255, p[0]]);
+                }
+                RawImage2d {
+                    data: Cow::Owned(data),
+                    ..raw
+                }
+            }
+        };
+        let img = RgbaImage::from_raw(raw.width, raw.height, raw.data.into_owned())
+            .expect("texture data does not match its size");
+        // OpenGL stores the bottom row first.
+        image::imageops::flip_vertical(&img)
+    }
+
+    /// Saves the texture as a PNG file, with the first row at the top.
+    #[cfg(feature = "image")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        self.read_image()
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(|e| e.to_string())
+    }
+
+    fn create_storage<F>(
+        factory: &F,
+        kind: StorageKind,
+        memory: &[u8],
+        size: [u32; 2],
+        settings: &TextureSettings,
+    ) -> Result<Self, TextureCreationError>
+    where
+        F: Facade,
+    {
+        use texture::{Filter, Wrap};
+
+        let f = |wrap| match wrap {
+            Wrap::ClampToEdge => SamplerWrapFunction::Clamp,
+            Wrap::Repeat => SamplerWrapFunction::Repeat,
+            Wrap::MirroredRepeat => SamplerWrapFunction::Mirror,
+            Wrap::ClampToBorder => SamplerWrapFunction::BorderClamp,
+        };
+
+        let wrap_u = f(settings.get_wrap_u());
+        let wrap_v = f(settings.get_wrap_v());
+
+        let (mipmaps, min) = if settings.get_generate_mipmap() {
+            let min = match (settings.get_min(), settings.get_mipmap()) {
+                (Filter::Linear, Filter::Linear) => MinifySamplerFilter::LinearMipmapLinear,
+                (Filter::Linear, Filter::Nearest) => MinifySamplerFilter::LinearMipmapNearest,
+                (Filter::Nearest, Filter::Linear) => MinifySamplerFilter::NearestMipmapLinear,
+                (Filter::Nearest, Filter::Nearest) => MinifySamplerFilter::NearestMipmapNearest,
+            };
+            (MipmapsOption::AutoGeneratedMipmaps, min)
+        } else {
+            let min = match settings.get_min() {
+                Filter::Linear => MinifySamplerFilter::Linear,
+                Filter::Nearest => MinifySamplerFilter::Nearest,
+            };
+            (MipmapsOption::NoMipmap, min)
+        };
+        let mag = match settings.get_mag() {
+            Filter::Linear => MagnifySamplerFilter::Linear,
+            Filter::Nearest => MagnifySamplerFilter::Nearest,
+        };
+
+        let storage = match kind {
+            StorageKind::Srgb => TextureStorage::Srgb(SrgbTexture2d::with_mipmaps(
+                factory,
+                RawImage2d::from_raw_rgba_reversed(memory, (size[0], size[1])),
+                mipmaps,
+            )?),
+            StorageKind::Linear => TextureStorage::Linear(Texture2d::with_format(
+                factory,
+                RawImage2d::from_raw_rgba_reversed(memory, (size[0], size[1])),
+                UncompressedFloatFormat::U8U8U8U8,
+                mipmaps,
+            )?),
+            StorageKind::Alpha => TextureStorage::Alpha(Texture2d::with_format(
+                factory,
+                raw_alpha_reversed(memory, size),
+                UncompressedFloatFormat::U8,
+                mipmaps,
+            )?),
+        };
+        Ok(Texture(Rc::new(storage), [wrap_u, wrap_v], (min, mag)))
+    }
+}
+
+// Decodes an image in any format supported by the `image` crate.
+#[cfg(feature = "image")]
+pub(crate) fn decode<R: BufRead + Seek>(reader: R, flip: Flip) -> Result<RgbaImage, String> {
+    let img = ImageReader::new(reader)
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())?;
+    Ok(to_rgba(img, flip))
+}
+
+// Opens an image file in any format supported by the `image` crate.
+#[cfg(feature = "image")]
+pub(crate) fn open<P: AsRef<Path>>(path: P, flip: Flip) -> Result<RgbaImage, String> {
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    Ok(to_rgba(img, flip))
+}
+
+// Converts an image to RGBA, flipping it if needed.
+#[cfg(feature = "image")]
+fn to_rgba(img: DynamicImage, flip: Flip) -> RgbaImage {
+    let img = match img {
+        DynamicImage::ImageRgba8(img) => img,
+        img => img.to_rgba8(),
+    };
+    if flip == Flip::Vertical {
+        image::imageops::flip_vertical(&img)
+    } else {
+        img
+    }
+}
+
+// Multiplies sRGB encoded colors with their alpha in linear space.
+fn premultiply(memory: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(memory.len());
+    for p in memory.chunks(4) {
+        let c = |i: usize| p[i] as f32 / 255.0;
+        let [r, g, b, a] = gamma_srgb_to_linear([c(0), c(1), c(2), c(3)]);
+        let [r, g, b, _] = gamma_linear_to_srgb([r * a, g * a, b * a, a]);
+        let byte = |v: f32| (v * 255.0).round() as u8;
+        data.extend_from_slice(&[byte(r), byte(g), byte(b), p[3]]);
+    }
+    data
+}
+
+// Creates a single channel image with rows in OpenGL order.
+fn raw_alpha_reversed(memory: &[u8], size: [u32; 2]) -> RawImage2d<'_, u8> {
+    RawImage2d {
+        data: Cow::Owned(texture::ops::flip_vertical(memory, size, 1)),
+        width: size[0],
+        height: size[1],
+        format: ClientFormat::U8,
+    }
 }
 
 impl ImageSize for Texture {
     fn get_size(&self) -> (u32, u32) {
-        let ref tex = self.0;
-        (tex.get_width(), tex.get_height().unwrap())
+        match *self.0 {
+            TextureStorage::Srgb(ref tex) => tex.dimensions(),
+            TextureStorage::Linear(ref tex) | TextureStorage::Alpha(ref tex) => tex.dimensions(),
+        }
+    }
+}
+
+/// Binds the texture with its wrap functions and filters.
+impl AsUniformValue for &Texture {
+    fn as_uniform_value(&self) -> UniformValue<'_> {
+        self.uniform_value()
     }
 }
 
@@ -139,30 +564,16 @@ where
 {
     fn create<S: Into<[u32; 2]>>(
         factory: &mut F,
-        _format: Format,
+        format: Format,
         memory: &[u8],
         size: S,
         settings: &TextureSettings,
     ) -> Result<Self, Self::Error> {
-        use texture::Wrap;
-        let size = size.into();
-
-        let f = |wrap| match wrap {
-            Wrap::ClampToEdge => SamplerWrapFunction::Clamp,
-            Wrap::Repeat => SamplerWrapFunction::Repeat,
-            Wrap::MirroredRepeat => SamplerWrapFunction::Mirror,
-            Wrap::ClampToBorder => SamplerWrapFunction::BorderClamp,
-        };
-
-        let wrap_u = f(settings.get_wrap_u());
-        let wrap_v = f(settings.get_wrap_v());
-        Ok(Texture(
-            SrgbTexture2d::new(
-                factory,
-                RawImage2d::from_raw_rgba_reversed(memory, (size[0], size[1]))
-            )?,
-            [wrap_u, wrap_v],
-        ))
+        match format {
+            Format::Rgba8 => {
+                Texture::create_storage(factory, StorageKind::Srgb, memory, size.into(), settings)
+            }
+        }
     }
 }
 
@@ -174,7 +585,7 @@ where
     fn update<O: Into<[u32; 2]>, S: Into<[u32; 2]>>(
         &mut self,
         factory: &mut F,
-        _format: Format,
+        format: Format,
         memory: &[u8],
         offset: O,
         size: S,
@@ -184,15 +595,28 @@ where
         let offset = offset.into();
         let size = size.into();
         let (_, h) = self.get_size();
-        self.0.write(
-            Rect {
-                left: offset[0],
-                bottom: h - offset[1] - size[1],
-                width: size[0],
-                height: size[1],
+        let rect = Rect {
+            left: offset[0],
+            bottom: h - offset[1] - size[1],
+            width: size[0],
+            height: size[1],
+        };
+        match format {
+            Format::Rgba8 => match *self.0 {
+                TextureStorage::Srgb(ref tex) => tex.write(
+                    rect,
+                    RawImage2d::from_raw_rgba_reversed(memory, (size[0], size[1])),
+                ),
+                TextureStorage::Linear(ref tex) => tex.write(
+                    rect,
+                    RawImage2d::from_raw_rgba_reversed(memory, (size[0], size[1])),
+                ),
+                TextureStorage::Alpha(ref tex) => {
+                    let alpha = memory.chunks(4).map(|p| p[3]).collect::<Vec<_>>();
+                    tex.write(rect, raw_alpha_reversed(&alpha, size))
+                }
             },
-            RawImage2d::from_raw_rgba_reversed(memory, (size[0], size[1])),
-        );
+        }
         Ok(())
     }
 }
@@ -0,0 +1,80 @@
+//! Shader sources and selection of the shading language version.
+//!
+//! The shaders here are not provided by `piston-shaders_graphics2d`,
+//! and mostly share the other stage with the corresponding shaders there.
+
+use glium::backend::Facade;
+use glium::{Api, Version};
+use shader_version::glsl::GLSL;
+
+pub mod blend;
+pub mod sprite;
+pub mod textured_alpha;
+pub mod textured_color_alpha;
+pub mod textured_slots;
+pub mod transformed_colored;
+pub mod transformed_textured;
+pub mod transformed_textured_color;
+
+/// The shading language used by the built-in shaders.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShaderVersion {
+    /// GLSL for desktop OpenGL.
+    Glsl(GLSL),
+    /// GLSL ES 1.00, for OpenGL ES 2.0 and WebGL 1.
+    Es100,
+    /// GLSL ES 3.00, for OpenGL ES 3.0 and WebGL 2.
+    Es300,
+}
+
+impl ShaderVersion {
+    /// Picks the best shading language supported by the context.
+    ///
+    /// Returns `None` when the context supports none of the built-in shaders.
+    pub fn detect<W>(window: &W) -> Option<ShaderVersion>
+    where
+        W: Facade,
+    {
+        let context = window.get_context();
+        let supported =
+            |api, major, minor| context.is_glsl_version_supported(&Version(api, major, minor));
+        if supported(Api::Gl, 1, 50) {
+            Some(ShaderVersion::Glsl(GLSL::V1_50))
+        } else if supported(Api::Gl, 1, 20) {
+            Some(ShaderVersion::Glsl(GLSL::V1_20))
+        } else if supported(Api::GlEs, 3, 0) {
+            Some(ShaderVersion::Es300)
+        } else if supported(Api::GlEs, 1, 0) {
+            Some(ShaderVersion::Es100)
+        } else {
+            None
+        }
+    }
+}
+
+// Makes a fragment shader write sRGB encoded colors,
+// by renaming its `main` and calling it from one that encodes the output.
+pub(crate) fn encode_srgb(source: &str, version: ShaderVersion) -> String {
+    let output = match version {
+        ShaderVersion::Glsl(glsl) if glsl < GLSL::V1_50 => "gl_FragColor",
+        ShaderVersion::Glsl(_) | ShaderVersion::Es300 => "o_Color",
+        ShaderVersion::Es100 => "gl_FragColor",
+    };
+    format!(
+        "{}
+vec3 linear_to_srgb(vec3 c)
+{{
+    c = max(c, 0.0);
+    return mix(c * 12.92, 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055, step(0.0031308, c));
+}}
+
+void main()
+{{
+    linear_main();
+    {output}.rgb = linear_to_srgb({output}.rgb);
+}}
+",
+        source.replacen("void main()", "void linear_main()", 1),
+        output = output
+    )
+}
@@ -0,0 +1,11 @@
+//! Shaders for textured color rendering with single channel alpha textures.
+
+/// Fragment shader for GLSL 1.20
+pub const FRAGMENT_GLSL_120: &[u8] = include_bytes!("120.glslf");
+/// Fragment shader for GLSL 1.50
+pub const FRAGMENT_GLSL_150_CORE: &[u8] = include_bytes!("150_core.glslf");
+
+/// Fragment shader for GLSL ES 1.00, without version directive
+pub const FRAGMENT_GLSL_120_WEBGL: &[u8] = include_bytes!("120_webgl.glslf");
+/// Fragment shader for GLSL ES 3.00, without version directive
+pub const FRAGMENT_GLSL_150_CORE_WEBGL: &[u8] = include_bytes!("150_core_webgl.glslf");
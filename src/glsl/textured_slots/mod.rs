@@ -0,0 +1,24 @@
+//! Shaders for textured rendering with vertex colors and several textures.
+//!
+//! Each vertex has the index of the texture slot it samples,
+//! so triangles using different textures are drawn with one draw call.
+
+/// Vertex shader for GLSL 1.20
+pub const VERTEX_GLSL_120: &[u8] = include_bytes!("120.glslv");
+/// Vertex shader for GLSL 1.50
+pub const VERTEX_GLSL_150_CORE: &[u8] = include_bytes!("150_core.glslv");
+
+/// Vertex shader for GLSL ES 1.00, without version directive
+pub const VERTEX_GLSL_120_WEBGL: &[u8] = include_bytes!("120_webgl.glslv");
+/// Vertex shader for GLSL ES 3.00, without version directive
+pub const VERTEX_GLSL_150_CORE_WEBGL: &[u8] = include_bytes!("150_core_webgl.glslv");
+
+/// Fragment shader for GLSL 1.20
+pub const FRAGMENT_GLSL_120: &[u8] = include_bytes!("120.glslf");
+/// Fragment shader for GLSL 1.50
+pub const FRAGMENT_GLSL_150_CORE: &[u8] = include_bytes!("150_core.glslf");
+
+/// Fragment shader for GLSL ES 1.00, without version directive
+pub const FRAGMENT_GLSL_120_WEBGL: &[u8] = include_bytes!("120_webgl.glslf");
+/// Fragment shader for GLSL ES 3.00, without version directive
+pub const FRAGMENT_GLSL_150_CORE_WEBGL: &[u8] = include_bytes!("150_core_webgl.glslf");
@@ -0,0 +1,13 @@
+//! Vertex shaders for colored rendering with a transform and tint.
+//!
+//! Drawn with the fragment shaders for colored rendering.
+
+/// Vertex shader for GLSL 1.20
+pub const VERTEX_GLSL_120: &[u8] = include_bytes!("120.glslv");
+/// Vertex shader for GLSL 1.50
+pub const VERTEX_GLSL_150_CORE: &[u8] = include_bytes!("150_core.glslv");
+
+/// Vertex shader for GLSL ES 1.00, without version directive
+pub const VERTEX_GLSL_120_WEBGL: &[u8] = include_bytes!("120_webgl.glslv");
+/// Vertex shader for GLSL ES 3.00, without version directive
+pub const VERTEX_GLSL_150_CORE_WEBGL: &[u8] = include_bytes!("150_core_webgl.glslv");
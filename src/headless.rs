@@ -0,0 +1,165 @@
+use glium::backend::{Backend, Context, Facade};
+use glium::IncompatibleOpenGl;
+use std::ops::Deref;
+use std::rc::Rc;
+
+#[cfg(feature = "headless")]
+use glium::SwapBuffersError;
+#[cfg(feature = "headless")]
+use glutin::api::egl::context::PossiblyCurrentContext;
+#[cfg(feature = "headless")]
+use glutin::api::egl::device::Device;
+#[cfg(feature = "headless")]
+use glutin::api::egl::display::Display;
+#[cfg(feature = "headless")]
+use glutin::config::{Api, ConfigSurfaceTypes, ConfigTemplateBuilder};
+#[cfg(feature = "headless")]
+use glutin::context::{ContextApi, ContextAttributesBuilder};
+#[cfg(feature = "headless")]
+use glutin::prelude::*;
+#[cfg(feature = "headless")]
+use std::error::Error;
+#[cfg(feature = "headless")]
+use std::ffi::CString;
+#[cfg(feature = "headless")]
+use std::os::raw::c_void;
+
+/// A glium context without a window.
+///
+/// With the `headless` feature, `Headless::egl` creates a surfaceless EGL context,
+/// which renders on machines without a display server, for example on CI.
+/// `Headless::egl_gles` does the same with OpenGL ES.
+/// Other OpenGL back-ends can be wrapped with `Headless::new`.
+///
+/// The context has no default framebuffer, so render with
+/// `Glium2d::draw_to_texture` or `Glium2d::draw_to_image`.
+pub struct Headless {
+    /// Glium context.
+    pub context: Rc<Context>,
+}
+
+impl Deref for Headless {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        &self.context
+    }
+}
+
+impl Clone for Headless {
+    fn clone(&self) -> Headless {
+        Headless {
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl Headless {
+    /// Creates new Headless from an OpenGL back-end.
+    ///
+    /// # Safety
+    ///
+    /// The OpenGL context of the back-end must be newly created.
+    pub unsafe fn new<B>(backend: B) -> Result<Self, IncompatibleOpenGl>
+    where
+        B: Backend + 'static,
+    {
+        let check_current = cfg!(feature = "check_current_window");
+        Context::new(backend, check_current, Default::default()).map(|context| Headless { context })
+    }
+
+    /// Creates a new Headless with a surfaceless EGL context.
+    ///
+    /// Uses the first EGL device that can create a context without a surface,
+    /// such as a GPU render node or Mesa's software renderer.
+    #[cfg(feature = "headless")]
+    pub fn egl() -> Result<Self, Box<dyn Error>> {
+        Headless::egl_api(Api::OPENGL, ContextApi::OpenGl(None))
+    }
+
+    /// Creates a new Headless with a surfaceless OpenGL ES 3 context.
+    ///
+    /// Useful for testing the OpenGL ES code paths on a desktop machine.
+    #[cfg(feature = "headless")]
+    pub fn egl_gles() -> Result<Self, Box<dyn Error>> {
+        Headless::egl_api(Api::GLES3, ContextApi::Gles(None))
+    }
+
+    #[cfg(feature = "headless")]
+    fn egl_api(api: Api, context_api: ContextApi) -> Result<Self, Box<dyn Error>> {
+        let mut last_error: Box<dyn Error> = "No EGL devices".into();
+        for device in Device::query_devices()? {
+            match EglBackend::new(&device, api, context_api) {
+                Ok(backend) => return Ok(unsafe { Headless::new(backend)? }),
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+impl Facade for Headless {
+    fn get_context(&self) -> &Rc<Context> {
+        &self.context
+    }
+}
+
+// An EGL context made current without a surface.
+#[cfg(feature = "headless")]
+struct EglBackend {
+    display: Display,
+    context: PossiblyCurrentContext,
+}
+
+#[cfg(feature = "headless")]
+impl EglBackend {
+    fn new(
+        device: &Device,
+        api: Api,
+        context_api: ContextApi,
+    ) -> Result<EglBackend, Box<dyn Error>> {
+        let display = unsafe { Display::with_device(device, None)? };
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .with_api(api)
+            .build();
+        let config = unsafe { display.find_configs(template)? }
+            .next()
+            .ok_or("No EGL config without surface")?;
+        let attributes = ContextAttributesBuilder::new()
+            .with_context_api(context_api)
+            .build(None);
+        let context = unsafe { display.create_context(&config, &attributes)? };
+        let context = context.make_current_surfaceless()?;
+        Ok(EglBackend { display, context })
+    }
+}
+
+#[cfg(feature = "headless")]
+unsafe impl Backend for EglBackend {
+    fn swap_buffers(&self) -> Result<(), SwapBuffersError> {
+        Ok(())
+    }
+
+    unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        match CString::new(symbol) {
+            Ok(symbol) => self.display.get_proc_address(&symbol) as *const c_void,
+            Err(_) => ::std::ptr::null(),
+        }
+    }
+
+    fn get_framebuffer_dimensions(&self) -> (u32, u32) {
+        // There is no default framebuffer.
+        (0, 0)
+    }
+
+    fn is_current(&self) -> bool {
+        self.context.is_current()
+    }
+
+    unsafe fn make_current(&self) {
+        self.context
+            .make_current_surfaceless()
+            .expect("failed to make EGL context current");
+    }
+}
@@ -4,7 +4,11 @@
 
 #[macro_use(uniform, implement_vertex)]
 extern crate glium;
+#[cfg(feature = "headless")]
+extern crate glutin;
 extern crate graphics;
+#[cfg(feature = "image")]
+extern crate image;
 extern crate shader_version;
 extern crate shaders_graphics2d as shaders;
 extern crate texture;
@@ -17,12 +21,45 @@ pub use window::GliumWindow;
 /// Stores textures for text rendering.
 pub type GlyphCache<'a, F> = graphics::glyph_cache::rusttype::GlyphCache<'a, F, Texture>;
 
+#[cfg(feature = "image")]
+pub use atlas::{Atlas, AtlasRegion};
 pub use back_end::{Glium2d, GliumGraphics};
-pub use glium_texture::{Flip, Texture};
+pub use blend::{AdvancedBlend, BlendEquation};
+pub use color_space::ColorSpace;
+pub use display_list::{Command, DisplayList};
+pub use error::Glium2dError;
+pub use glium_texture::{Flip, Texture, TextureStorage};
+pub use glsl::ShaderVersion;
+pub use headless::Headless;
+pub use material::{Material, MaterialUniform};
+pub use post_process::PostProcess;
+pub use sprite::{Sprite, SpriteBatch};
+pub use static_mesh::{MeshRecorder, StaticMesh};
+pub use stats::FrameStats;
 pub use texture::*;
+#[cfg(feature = "image")]
+pub use texture_loader::TextureLoader;
+pub use tilemap::Tilemap;
 
+#[cfg(feature = "image")]
+mod atlas;
 mod back_end;
+mod blend;
+mod color_space;
+mod display_list;
 mod draw_state;
+mod error;
 mod glium_texture;
+mod glsl;
+mod headless;
+mod material;
+mod post_process;
+mod sprite;
+mod static_mesh;
+mod stats;
+#[cfg(feature = "image")]
+mod texture_loader;
+mod tilemap;
+mod vertex_stream;
 #[cfg(feature = "glium_window")]
 mod window;
@@ -0,0 +1,119 @@
+use glium::uniforms::{UniformValue, Uniforms};
+use glium::Program;
+use std::rc::Rc;
+use Texture;
+
+/// A uniform value of a material.
+#[derive(Clone)]
+pub enum MaterialUniform {
+    /// A `float`.
+    Float(f32),
+    /// A `vec2`.
+    Vec2([f32; 2]),
+    /// A `vec3`.
+    Vec3([f32; 3]),
+    /// A `vec4`.
+    Vec4([f32; 4]),
+    /// An `int`.
+    Int(i32),
+    /// A `bool`.
+    Bool(bool),
+    /// A `mat4`.
+    Mat4([[f32; 4]; 4]),
+    /// A `sampler2D`, using the filters and wrap functions of the texture.
+    Texture(Texture),
+}
+
+impl MaterialUniform {
+    fn uniform_value(&self) -> UniformValue<'_> {
+        match *self {
+            MaterialUniform::Float(v) => UniformValue::Float(v),
+            MaterialUniform::Vec2(v) => UniformValue::Vec2(v),
+            MaterialUniform::Vec3(v) => UniformValue::Vec3(v),
+            MaterialUniform::Vec4(v) => UniformValue::Vec4(v),
+            MaterialUniform::Int(v) => UniformValue::SignedInt(v),
+            MaterialUniform::Bool(v) => UniformValue::Bool(v),
+            MaterialUniform::Mat4(v) => UniformValue::Mat4(v),
+            MaterialUniform::Texture(ref texture) => texture.uniform_value(),
+        }
+    }
+}
+
+/// A user program with uniforms, drawn instead of the built-in shaders.
+///
+/// The program receives the vertex attributes of the geometry it is used for:
+///
+/// - `tri_list` and `tri_list_c`: `pos: vec2` and `color: vec4`
+/// - `tri_list_uv`: `pos: vec2`, `uv: vec2`, `color: vec4` and `slot: float`,
+///   with the uniforms `color: vec4` and `s_texture: sampler2D`;
+///   the vertex color equals the `color` uniform, and the slot is always 0
+/// - `tri_list_uv_c`: `pos: vec2`, `uv: vec2` and `color: vec4`,
+///   with the uniform `s_texture: sampler2D`
+///
+/// Positions are in normalized device coordinates and colors are linear,
+/// multiplied with their alpha when using `Glium2d::set_premultiplied_alpha`.
+/// Uniforms that are not used by the program are ignored.
+#[derive(Clone)]
+pub struct Material {
+    program: Rc<Program>,
+    uniforms: Vec<(String, MaterialUniform)>,
+}
+
+impl Material {
+    /// Creates a new material without uniforms.
+    pub fn new(program: Program) -> Material {
+        Material::from_rc(Rc::new(program))
+    }
+
+    /// Creates a new material sharing a program.
+    pub fn from_rc(program: Rc<Program>) -> Material {
+        Material {
+            program,
+            uniforms: vec![],
+        }
+    }
+
+    /// Gets the program.
+    pub fn get_program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Gets the value of a uniform.
+    pub fn get(&self, name: &str) -> Option<&MaterialUniform> {
+        self.uniforms
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Sets the value of a uniform.
+    pub fn set(&mut self, name: &str, value: MaterialUniform) {
+        match self.uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value,
+            None => self.uniforms.push((name.to_owned(), value)),
+        }
+    }
+
+    /// Sets the value of a uniform.
+    pub fn uniform(mut self, name: &str, value: MaterialUniform) -> Self {
+        self.set(name, value);
+        self
+    }
+}
+
+// Adds the uniforms of the current material to the built-in uniforms.
+pub(crate) struct WithMaterial<'m, U> {
+    pub uniforms: U,
+    pub material: Option<&'m Material>,
+}
+
+impl<'m, U: Uniforms> Uniforms for WithMaterial<'m, U> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut f: F) {
+        self.uniforms.visit_values(&mut f);
+        if let Some(material) = self.material {
+            for (name, value) in &material.uniforms {
+                f(name, value.uniform_value());
+            }
+        }
+    }
+}
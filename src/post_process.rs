@@ -0,0 +1,164 @@
+use back_end::texture_framebuffer;
+use glium::backend::Facade;
+use glium::framebuffer::{DepthStencilRenderBuffer, SimpleFrameBuffer};
+use glium::texture::DepthStencilFormat;
+use glium::Surface;
+use graphics::{self, DrawState, Image, ImageSize, Viewport};
+use texture::TextureSettings;
+use {Glium2d, Glium2dError, GliumGraphics, Material, MaterialUniform, Texture};
+
+/// Full-screen effects applied to 2D graphics.
+///
+/// The scene is rendered into an offscreen texture,
+/// then each pass draws the previous result into the other of two textures,
+/// and the last result is drawn to the target.
+///
+/// A pass is a material for textured geometry,
+/// see `Material` for the vertex attributes and uniforms it receives.
+/// The previous result is bound to `s_texture`,
+/// and its size in pixels to `resolution: vec2`.
+pub struct PostProcess {
+    passes: Vec<Material>,
+    settings: TextureSettings,
+    // The ping-pong textures with their depth and stencil buffers.
+    targets: Option<[(Texture, DepthStencilRenderBuffer); 2]>,
+}
+
+impl PostProcess {
+    /// Creates a new `PostProcess` without passes.
+    pub fn new() -> PostProcess {
+        PostProcess {
+            passes: vec![],
+            settings: TextureSettings::new(),
+            targets: None,
+        }
+    }
+
+    /// Gets the passes.
+    pub fn get_passes(&self) -> &[Material] {
+        &self.passes
+    }
+
+    /// Gets the passes mutably, for example to update uniforms.
+    pub fn get_passes_mut(&mut self) -> &mut Vec<Material> {
+        &mut self.passes
+    }
+
+    /// Adds a pass at the end of the chain.
+    pub fn push_pass(&mut self, material: Material) {
+        self.passes.push(material);
+    }
+
+    /// Adds a pass at the end of the chain.
+    pub fn pass(mut self, material: Material) -> Self {
+        self.push_pass(material);
+        self
+    }
+
+    /// Gets the texture settings of the offscreen targets.
+    pub fn get_texture_settings(&self) -> &TextureSettings {
+        &self.settings
+    }
+
+    /// Sets the texture settings of the offscreen targets.
+    pub fn set_texture_settings(&mut self, settings: TextureSettings) {
+        self.settings = settings;
+        self.targets = None;
+    }
+
+    /// Sets the texture settings of the offscreen targets.
+    pub fn texture_settings(mut self, settings: TextureSettings) -> Self {
+        self.set_texture_settings(settings);
+        self
+    }
+
+    /// Renders 2D graphics with the passes applied.
+    ///
+    /// The offscreen targets have the size of the frame buffer, `viewport.draw_size`,
+    /// and are kept between frames until the window is resized.
+    /// The scene is rendered with the same transform as `Glium2d::draw`.
+    pub fn draw<W, S, F, U>(
+        &mut self,
+        g2d: &mut Glium2d,
+        window: &mut W,
+        target: &mut S,
+        viewport: Viewport,
+        f: F,
+    ) -> Result<U, Glium2dError>
+    where
+        W: Facade,
+        S: Surface,
+        F: FnOnce(graphics::Context, &mut GliumGraphics<SimpleFrameBuffer>) -> U,
+    {
+        let [w, h] = viewport.draw_size;
+        let resize = match self.targets {
+            Some(ref targets) => targets[0].0.get_size() != (w, h),
+            None => true,
+        };
+        if resize {
+            let mut new_target = || -> Result<_, Glium2dError> {
+                Ok((
+                    Texture::empty_with_size(window, w, h, &self.settings)?,
+                    DepthStencilRenderBuffer::new(window, DepthStencilFormat::I24I8, w, h)?,
+                ))
+            };
+            self.targets = Some([new_target()?, new_target()?]);
+        }
+        let targets = self.targets.as_ref().unwrap();
+        let mut framebuffers = [
+            texture_framebuffer(window, &targets[0].0, &targets[0].1)?,
+            texture_framebuffer(window, &targets[1].0, &targets[1].1)?,
+        ];
+
+        let scene_viewport = Viewport {
+            rect: [0, 0, w as i32, h as i32],
+            ..viewport
+        };
+        let res = g2d.draw_to_framebuffer(&mut framebuffers[0], scene_viewport, f)?;
+
+        let pass_viewport = Viewport {
+            rect: [0, 0, w as i32, h as i32],
+            draw_size: [w, h],
+            window_size: [w as f64, h as f64],
+        };
+        let mut src = 0;
+        for pass in &self.passes {
+            let material = pass
+                .clone()
+                .uniform("resolution", MaterialUniform::Vec2([w as f32, h as f32]));
+            g2d.draw_to_framebuffer(&mut framebuffers[1 - src], pass_viewport, |c, g| {
+                g.with_material(&material, |g| {
+                    blit(&targets[src].0, [w as f64, h as f64], c, g)
+                });
+            })?;
+            src = 1 - src;
+        }
+
+        g2d.try_draw(target, viewport, |c, g| {
+            blit(&targets[src].0, viewport.window_size, c, g)
+        })?;
+        Ok(res)
+    }
+}
+
+impl Default for PostProcess {
+    fn default() -> PostProcess {
+        PostProcess::new()
+    }
+}
+
+// Draws a texture over the whole target, replacing its contents.
+fn blit<S: Surface>(
+    texture: &Texture,
+    size: [f64; 2],
+    c: graphics::Context,
+    g: &mut GliumGraphics<S>,
+) {
+    let draw_state = DrawState {
+        blend: None,
+        ..Default::default()
+    };
+    Image::new()
+        .rect([0.0, 0.0, size[0], size[1]])
+        .draw(texture, &draw_state, c.transform, g);
+}
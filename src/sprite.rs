@@ -0,0 +1,103 @@
+use back_end::same_texture;
+use graphics::math::Matrix2d;
+use Texture;
+
+/// A textured quad drawn as part of a `SpriteBatch`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    /// The transform, such as `c.transform.trans(x, y)`.
+    pub transform: Matrix2d,
+    /// The part of the texture to draw, `[x, y, w, h]` in pixels.
+    ///
+    /// The sprite covers `[0, 0, w, h]` before the transform,
+    /// like `graphics::Image` with the same source rectangle.
+    pub src_rect: [f64; 4],
+    /// The color multiplied with the texture.
+    pub color: [f32; 4],
+}
+
+impl Sprite {
+    /// Creates a new white sprite.
+    pub fn new(src_rect: [f64; 4], transform: Matrix2d) -> Sprite {
+        Sprite {
+            transform,
+            src_rect,
+            color: [1.0; 4],
+        }
+    }
+
+    /// Sets the color multiplied with the texture.
+    pub fn color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+// The sprites using the same texture.
+pub(crate) struct SpriteGroup {
+    pub texture: Texture,
+    pub sprites: Vec<Sprite>,
+}
+
+/// Sprites drawn with one instanced draw call per texture.
+///
+/// This is much faster than `graphics::Image` for many quads,
+/// such as particles or tiles, because only the transform, source rectangle
+/// and color of each sprite are uploaded.
+/// Sprites using the same texture are drawn in the order they were added,
+/// and textures in the order they were first used,
+/// so overlapping sprites with different textures might not be drawn in order.
+/// Use an `Atlas` to draw everything with one texture.
+///
+/// Draw with `GliumGraphics::draw_sprites`.
+/// The batch can be kept between frames, or cleared and refilled.
+pub struct SpriteBatch {
+    groups: Vec<SpriteGroup>,
+}
+
+impl SpriteBatch {
+    /// Creates a new empty batch.
+    pub fn new() -> SpriteBatch {
+        SpriteBatch { groups: vec![] }
+    }
+
+    /// Adds a sprite.
+    pub fn push(&mut self, texture: &Texture, sprite: Sprite) {
+        match self
+            .groups
+            .iter_mut()
+            .find(|group| same_texture(Some(&group.texture), texture))
+        {
+            Some(group) => group.sprites.push(sprite),
+            None => self.groups.push(SpriteGroup {
+                texture: texture.clone(),
+                sprites: vec![sprite],
+            }),
+        }
+    }
+
+    /// Removes all sprites.
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+
+    /// Returns the number of sprites.
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(|group| group.sprites.len()).sum()
+    }
+
+    /// Returns `true` if the batch has no sprites.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+}
+
+impl Default for SpriteBatch {
+    fn default() -> SpriteBatch {
+        SpriteBatch::new()
+    }
+}
@@ -0,0 +1,279 @@
+use back_end::{linear_color, same_texture, PlainVertex, TexturedColorVertex, TexturedVertex};
+use glium::backend::Facade;
+use glium::vertex::Vertex;
+use glium::VertexBuffer;
+use graphics::{DrawState, Graphics};
+use std::cmp::min;
+use std::ops::Range;
+use {Glium2dError, Texture};
+
+/// A handle to geometry recorded once and kept on the GPU by `Glium2d`.
+///
+/// Created with `Glium2d::record_static_mesh`,
+/// and drawn with `GliumGraphics::draw_static_mesh`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StaticMesh(pub(crate) usize);
+
+// A part of a static mesh, drawn with one draw call.
+pub(crate) enum MeshPart {
+    ClearColor([f32; 4]),
+    ClearStencil(u8),
+    Colored {
+        draw_state: DrawState,
+        vertices: Range<usize>,
+    },
+    Textured {
+        draw_state: DrawState,
+        color: [f32; 4],
+        texture: Texture,
+        vertices: Range<usize>,
+    },
+    TexturedColor {
+        draw_state: DrawState,
+        texture: Texture,
+        vertices: Range<usize>,
+    },
+}
+
+impl MeshPart {
+    // Extends the vertices with those of the next part when it has the same state.
+    fn merge(&mut self, next: &MeshPart) -> bool {
+        use self::MeshPart::*;
+
+        let same = match (&*self, next) {
+            (&Colored { draw_state: a, .. }, &Colored { draw_state: b, .. }) => a == b,
+            (
+                &Textured {
+                    draw_state: a,
+                    color: c,
+                    texture: ref t,
+                    ..
+                },
+                &Textured {
+                    draw_state: b,
+                    color: d,
+                    texture: ref u,
+                    ..
+                },
+            ) => a == b && c == d && same_texture(Some(t), u),
+            (
+                &TexturedColor {
+                    draw_state: a,
+                    texture: ref t,
+                    ..
+                },
+                &TexturedColor {
+                    draw_state: b,
+                    texture: ref u,
+                    ..
+                },
+            ) => a == b && same_texture(Some(t), u),
+            _ => false,
+        };
+        if !same {
+            return false;
+        }
+        let end = match *next {
+            Colored { ref vertices, .. }
+            | Textured { ref vertices, .. }
+            | TexturedColor { ref vertices, .. } => vertices.end,
+            ClearColor(_) | ClearStencil(_) => return false,
+        };
+        match *self {
+            Colored {
+                ref mut vertices, ..
+            }
+            | Textured {
+                ref mut vertices, ..
+            }
+            | TexturedColor {
+                ref mut vertices, ..
+            } => vertices.end = end,
+            ClearColor(_) | ClearStencil(_) => {}
+        }
+        true
+    }
+}
+
+// The parts of a static mesh and their vertices.
+pub(crate) struct MeshData {
+    pub parts: Vec<MeshPart>,
+    pub plain: Option<VertexBuffer<PlainVertex>>,
+    pub textured: Option<VertexBuffer<TexturedVertex>>,
+    pub textured_color: Option<VertexBuffer<TexturedColorVertex>>,
+}
+
+/// Records graphics into a `StaticMesh`.
+///
+/// Consecutive triangles with the same draw state, color and texture
+/// are merged into one part of the mesh.
+pub struct MeshRecorder {
+    parts: Vec<MeshPart>,
+    plain: Vec<PlainVertex>,
+    textured: Vec<TexturedVertex>,
+    textured_color: Vec<TexturedColorVertex>,
+    // Whether colors are multiplied with their alpha.
+    premultiplied: bool,
+}
+
+impl MeshRecorder {
+    pub(crate) fn new(premultiplied: bool) -> MeshRecorder {
+        MeshRecorder {
+            parts: vec![],
+            plain: vec![],
+            textured: vec![],
+            textured_color: vec![],
+            premultiplied,
+        }
+    }
+
+    // Adds a part, merging it into the last part when they have the same state.
+    fn push(&mut self, part: MeshPart) {
+        let merged = match self.parts.last_mut() {
+            Some(last) => last.merge(&part),
+            None => false,
+        };
+        if !merged {
+            self.parts.push(part);
+        }
+    }
+
+    // Uploads the vertices.
+    pub(crate) fn upload<F: Facade>(self, window: &F) -> Result<MeshData, Glium2dError> {
+        fn buffer<F: Facade, V: Vertex>(
+            window: &F,
+            vertices: &[V],
+        ) -> Result<Option<VertexBuffer<V>>, Glium2dError> {
+            if vertices.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(VertexBuffer::immutable(window, vertices)?))
+            }
+        }
+
+        Ok(MeshData {
+            plain: buffer(window, &self.plain)?,
+            textured: buffer(window, &self.textured)?,
+            textured_color: buffer(window, &self.textured_color)?,
+            parts: self.parts,
+        })
+    }
+}
+
+impl Graphics for MeshRecorder {
+    type Texture = Texture;
+
+    fn clear_color(&mut self, color: [f32; 4]) {
+        self.parts.push(MeshPart::ClearColor(linear_color(
+            color,
+            self.premultiplied,
+        )));
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        self.parts.push(MeshPart::ClearStencil(value));
+    }
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        let color = linear_color(*color, self.premultiplied);
+        f(&mut |vertices: &[[f32; 2]]| {
+            let start = self.plain.len();
+            self.plain
+                .extend(vertices.iter().map(|&pos| PlainVertex { color, pos }));
+            let end = self.plain.len();
+            if start < end {
+                self.push(MeshPart::Colored {
+                    draw_state: *draw_state,
+                    vertices: start..end,
+                });
+            }
+        })
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        let premultiplied = self.premultiplied;
+        f(&mut |vertices: &[[f32; 2]], colors: &[[f32; 4]]| {
+            let start = self.plain.len();
+            self.plain.extend(
+                vertices
+                    .iter()
+                    .zip(colors)
+                    .map(|(&pos, &color)| PlainVertex {
+                        color: linear_color(color, premultiplied),
+                        pos,
+                    }),
+            );
+            let end = self.plain.len();
+            if start < end {
+                self.push(MeshPart::Colored {
+                    draw_state: *draw_state,
+                    vertices: start..end,
+                });
+            }
+        })
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &Texture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        let color = linear_color(*color, self.premultiplied);
+        f(&mut |vertices: &[[f32; 2]], texture_coords: &[[f32; 2]]| {
+            let len = min(vertices.len(), texture_coords.len());
+            let start = self.textured.len();
+            self.textured.extend((0..len).map(|i| TexturedVertex {
+                pos: vertices[i],
+                // FIXME: The `1.0 - ...` is because of a wrong convention
+                uv: [texture_coords[i][0], 1.0 - texture_coords[i][1]],
+            }));
+            let end = self.textured.len();
+            if start < end {
+                self.push(MeshPart::Textured {
+                    draw_state: *draw_state,
+                    color,
+                    texture: texture.clone(),
+                    vertices: start..end,
+                });
+            }
+        })
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &Texture, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        let premultiplied = self.premultiplied;
+        f(
+            &mut |vertices: &[[f32; 2]], texture_coords: &[[f32; 2]], colors: &[[f32; 4]]| {
+                let len = min(min(vertices.len(), texture_coords.len()), colors.len());
+                let start = self.textured_color.len();
+                self.textured_color
+                    .extend((0..len).map(|i| TexturedColorVertex {
+                        pos: vertices[i],
+                        // FIXME: The `1.0 - ...` is because of a wrong convention
+                        uv: [texture_coords[i][0], 1.0 - texture_coords[i][1]],
+                        color: linear_color(colors[i], premultiplied),
+                    }));
+                let end = self.textured_color.len();
+                if start < end {
+                    self.push(MeshPart::TexturedColor {
+                        draw_state: *draw_state,
+                        texture: texture.clone(),
+                        vertices: start..end,
+                    });
+                }
+            },
+        )
+    }
+}
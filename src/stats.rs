@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+/// Statistics of a frame rendered with `Glium2d`.
+///
+/// The statistics of offscreen draws, such as `Glium2d::draw_to_texture`,
+/// add up until the frame ends with `Glium2d::draw` or `Glium2d::try_draw`.
+///
+/// Collecting statistics is enabled with `Glium2d::set_collect_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// The number of draw calls.
+    pub draw_calls: usize,
+    /// The number of vertices drawn.
+    pub vertices: usize,
+    /// The number of draw calls caused by a change of draw state,
+    /// texture, color, material or kind of geometry.
+    pub state_flushes: usize,
+    /// The number of draw calls caused by a full vertex buffer.
+    pub overflow_flushes: usize,
+    /// The number of times the bound texture changed between draw calls.
+    ///
+    /// Each texture of a draw call that the previous textured draw call
+    /// in the frame did not bind counts as a change,
+    /// including the first texture of the frame.
+    pub texture_switches: usize,
+    /// The time the GPU spent on draw calls.
+    ///
+    /// The GPU time is read without waiting for the GPU,
+    /// so it belongs to an earlier frame, usually one or two frames behind.
+    /// This is `None` until the first measurement is done,
+    /// or when the context does not support timer queries.
+    pub gpu_time: Option<Duration>,
+}
@@ -0,0 +1,212 @@
+use glium::backend::Facade;
+use glium_texture::{decode, open};
+use image::RgbaImage;
+use std::any::Any;
+use std::borrow::Cow;
+use std::io::Cursor;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use texture::TextureSettings;
+use {Flip, Texture};
+
+// The encoded image to decode.
+enum Source {
+    Bytes(Cow<'static, [u8]>),
+    Path(PathBuf),
+}
+
+struct Job<K> {
+    key: K,
+    source: Source,
+    flip: Flip,
+    settings: TextureSettings,
+}
+
+// A decoded image waiting to be uploaded.
+struct Decoded<K> {
+    key: K,
+    image: Result<RgbaImage, String>,
+    settings: TextureSettings,
+}
+
+/// Decodes images on worker threads and uploads them as textures.
+///
+/// Decoding is the slow part of loading a texture and does not need the context,
+/// so it runs in the background while the thread owning the glium context keeps drawing.
+/// Call `upload` from that thread, for example once per frame,
+/// to create the textures of the images decoded so far.
+///
+/// Each image is identified by a key chosen when it is queued.
+///
+/// Dropping the loader stops the worker threads after their current image.
+pub struct TextureLoader<K> {
+    jobs: Sender<Job<K>>,
+    decoded: Receiver<Decoded<K>>,
+    pending: usize,
+}
+
+impl<K: Send + 'static> TextureLoader<K> {
+    /// Creates a new texture loader with a number of worker threads.
+    ///
+    /// Uses one thread when the number is 0.
+    pub fn new(threads: usize) -> TextureLoader<K> {
+        let (jobs, receiver) = channel::<Job<K>>();
+        let (sender, decoded) = channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..threads.max(1) {
+            let receiver = receiver.clone();
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                let job = match receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(err) => err.into_inner().recv(),
+                };
+                let job = match job {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                let (source, flip) = (job.source, job.flip);
+                // Report a panicking decoder as an error, so the worker keeps running.
+                let image = panic::catch_unwind(AssertUnwindSafe(|| match source {
+                    Source::Bytes(bytes) => decode(Cursor::new(bytes), flip),
+                    Source::Path(path) => open(path, flip),
+                }))
+                .unwrap_or_else(|payload| Err(panic_message(payload)));
+                let decoded = Decoded {
+                    key: job.key,
+                    image,
+                    settings: job.settings,
+                };
+                // Stop when the loader is dropped.
+                if sender.send(decoded).is_err() {
+                    break;
+                }
+            });
+        }
+        TextureLoader {
+            jobs,
+            decoded,
+            pending: 0,
+        }
+    }
+
+    /// Queues encoded image data in memory for decoding.
+    ///
+    /// Supports the formats of the `image` crate, detected from the data.
+    /// Returns an error when the worker threads have stopped.
+    pub fn load_bytes<B>(
+        &mut self,
+        key: K,
+        bytes: B,
+        flip: Flip,
+        settings: &TextureSettings,
+    ) -> Result<(), String>
+    where
+        B: Into<Cow<'static, [u8]>>,
+    {
+        self.queue(key, Source::Bytes(bytes.into()), flip, settings)
+    }
+
+    /// Queues an image file for loading and decoding.
+    ///
+    /// Returns an error when the worker threads have stopped.
+    pub fn load_path<P>(
+        &mut self,
+        key: K,
+        path: P,
+        flip: Flip,
+        settings: &TextureSettings,
+    ) -> Result<(), String>
+    where
+        P: Into<PathBuf>,
+    {
+        self.queue(key, Source::Path(path.into()), flip, settings)
+    }
+
+    fn queue(
+        &mut self,
+        key: K,
+        source: Source,
+        flip: Flip,
+        settings: &TextureSettings,
+    ) -> Result<(), String> {
+        let job = Job {
+            key,
+            source,
+            flip,
+            settings: *settings,
+        };
+        self.jobs
+            .send(job)
+            .map_err(|_| "The texture loader threads have stopped".to_string())?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Returns the number of images that are queued or not uploaded yet.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Uploads the images decoded so far, without waiting for the rest.
+    ///
+    /// Returns the textures with their keys, in the order they finished decoding.
+    /// Must be called from the thread owning the glium context.
+    pub fn upload<F>(&mut self, factory: &mut F) -> Vec<(K, Result<Texture, String>)>
+    where
+        F: Facade,
+    {
+        let decoded = self.decoded.try_iter().collect::<Vec<_>>();
+        self.create(factory, decoded)
+    }
+
+    /// Waits for all queued images and uploads them.
+    ///
+    /// Returns early with the images decoded so far when the worker threads have stopped.
+    /// Must be called from the thread owning the glium context.
+    pub fn finish<F>(&mut self, factory: &mut F) -> Vec<(K, Result<Texture, String>)>
+    where
+        F: Facade,
+    {
+        let decoded = self.decoded.iter().take(self.pending).collect::<Vec<_>>();
+        let textures = self.create(factory, decoded);
+        // The images that did not arrive are lost with their threads.
+        self.pending = 0;
+        textures
+    }
+
+    fn create<F>(
+        &mut self,
+        factory: &mut F,
+        decoded: Vec<Decoded<K>>,
+    ) -> Vec<(K, Result<Texture, String>)>
+    where
+        F: Facade,
+    {
+        self.pending -= decoded.len();
+        decoded
+            .into_iter()
+            .map(|decoded| {
+                let settings = decoded.settings;
+                let texture = decoded.image.and_then(|img| {
+                    Texture::from_image(factory, &img, &settings).map_err(|e| format!("{:?}", e))
+                });
+                (decoded.key, texture)
+            })
+            .collect()
+    }
+}
+
+// Gets the message of a panic in a worker thread.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(msg) => msg.to_string(),
+            Err(_) => "Image decoder panicked".to_string(),
+        },
+    }
+}
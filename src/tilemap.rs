@@ -0,0 +1,219 @@
+use back_end::{TexturedVertex, QUAD_CORNERS, QUAD_INDICES};
+use glium::backend::Facade;
+use glium::index::{IndexBuffer, IndexBufferSlice, PrimitiveType};
+use glium::VertexBuffer;
+use graphics::math::Matrix2d;
+use graphics::ImageSize;
+use {Glium2dError, Texture};
+
+/// A grid of tiles from a tileset texture, stored on the GPU.
+///
+/// The vertices are uploaded once, and again only for the tiles that change,
+/// instead of every frame like geometry drawn through `graphics`.
+/// Tile IDs number the tiles of the tileset row by row, starting at 0,
+/// and `None` leaves a tile empty.
+/// The tile at column `c` and row `r` covers `[c * w, r * h, w, h]` before the transform,
+/// where `[w, h]` is the tile size in pixels.
+///
+/// Use nearest filtering for the tileset, or extruded tiles like in an `Atlas`,
+/// so neighbouring tiles do not bleed in.
+/// Draw with `GliumGraphics::draw_tilemap`.
+pub struct Tilemap {
+    texture: Texture,
+    tile_size: [u32; 2],
+    columns: usize,
+    rows: usize,
+    tiles: Vec<Option<u32>>,
+    vertices: VertexBuffer<TexturedVertex>,
+    indices: IndexBuffer<u32>,
+    // The range of tiles changed since the last upload.
+    dirty: Option<(usize, usize)>,
+    // The visible tiles `[c0, r0, c1, r1]` the indices were written for,
+    // and the number of indices.
+    visible: Option<([usize; 4], usize)>,
+}
+
+impl Tilemap {
+    /// Creates a new tilemap with a number of columns and rows.
+    ///
+    /// The tiles are ordered row by row.
+    /// Returns an error if the number of tiles does not match the size.
+    pub fn new<F>(
+        factory: &mut F,
+        texture: Texture,
+        tile_size: [u32; 2],
+        [columns, rows]: [usize; 2],
+        tiles: &[Option<u32>],
+    ) -> Result<Tilemap, Glium2dError>
+    where
+        F: Facade,
+    {
+        if tiles.len() != columns * rows {
+            return Err(Glium2dError::TileCount(tiles.len(), [columns, rows]));
+        }
+        let mut tilemap = Tilemap {
+            vertices: VertexBuffer::empty_immutable(factory, 4 * tiles.len())?,
+            indices: IndexBuffer::empty_dynamic(
+                factory,
+                PrimitiveType::TrianglesList,
+                6 * tiles.len(),
+            )?,
+            texture,
+            tile_size,
+            columns,
+            rows,
+            tiles: tiles.to_vec(),
+            dirty: None,
+            visible: None,
+        };
+        if !tiles.is_empty() {
+            tilemap.dirty = Some((0, tiles.len()));
+            tilemap.upload();
+        }
+        Ok(tilemap)
+    }
+
+    /// Gets the tileset texture.
+    pub fn get_texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Gets the tile size in pixels.
+    pub fn get_tile_size(&self) -> [u32; 2] {
+        self.tile_size
+    }
+
+    /// Gets the number of columns and rows.
+    pub fn get_size(&self) -> [usize; 2] {
+        [self.columns, self.rows]
+    }
+
+    /// Gets the tile ID at a column and row.
+    pub fn get_tile(&self, column: usize, row: usize) -> Option<u32> {
+        self.tiles[self.index(column, row)]
+    }
+
+    /// Sets the tile ID at a column and row.
+    ///
+    /// Changed tiles are uploaded together the next time the tilemap is drawn.
+    pub fn set_tile(&mut self, column: usize, row: usize, tile: Option<u32>) {
+        let i = self.index(column, row);
+        if self.tiles[i] == tile {
+            return;
+        }
+        if self.tiles[i].is_none() || tile.is_none() {
+            self.visible = None;
+        }
+        self.tiles[i] = tile;
+        self.dirty = Some(match self.dirty {
+            Some((start, end)) => (start.min(i), end.max(i + 1)),
+            None => (i, i + 1),
+        });
+    }
+
+    fn index(&self, column: usize, row: usize) -> usize {
+        assert!(
+            column < self.columns && row < self.rows,
+            "tile out of bounds"
+        );
+        row * self.columns + column
+    }
+
+    // Writes the vertices of the changed tiles.
+    fn upload(&mut self) {
+        let (start, end) = match self.dirty.take() {
+            Some(dirty) => dirty,
+            None => return,
+        };
+        let [w, h] = self.tile_size;
+        let (tw, th) = self.texture.get_size();
+        let per_row = (tw / w.max(1)).max(1);
+        let vertices = (start..end)
+            .flat_map(|i| {
+                let (column, row) = ((i % self.columns) as f32, (i / self.columns) as f32);
+                let id = self.tiles[i].unwrap_or(0);
+                let src = [(id % per_row) * w, (id / per_row) * h];
+                QUAD_CORNERS.iter().map(move |&[cx, cy]| TexturedVertex {
+                    pos: [(column + cx) * w as f32, (row + cy) * h as f32],
+                    // FIXME: The `1.0 - ...` is because of a wrong convention
+                    uv: [
+                        (src[0] as f32 + cx * w as f32) / tw as f32,
+                        1.0 - (src[1] as f32 + cy * h as f32) / th as f32,
+                    ],
+                })
+            })
+            .collect::<Vec<_>>();
+        self.vertices
+            .slice(4 * start..4 * end)
+            .unwrap()
+            .write(&vertices);
+    }
+
+    // Uploads changed tiles and writes the indices of the tiles
+    // visible through a transform, returning the number of indices.
+    pub(crate) fn prepare(&mut self, transform: Matrix2d) -> usize {
+        self.upload();
+        let visible = self.visible_tiles(transform);
+        match self.visible {
+            Some((rect, n)) if rect == visible => return n,
+            _ => {}
+        }
+        let [c0, r0, c1, r1] = visible;
+        let indices = (r0..r1)
+            .flat_map(|row| (c0..c1).map(move |column| (row, column)))
+            .map(|(row, column)| row * self.columns + column)
+            .filter(|&i| self.tiles[i].is_some())
+            .flat_map(|i| QUAD_INDICES.iter().map(move |q| 4 * i as u32 + q))
+            .collect::<Vec<_>>();
+        if !indices.is_empty() {
+            self.indices.invalidate();
+            self.indices
+                .slice(0..indices.len())
+                .unwrap()
+                .write(&indices);
+        }
+        self.visible = Some((visible, indices.len()));
+        indices.len()
+    }
+
+    // Returns the range of tiles `[c0, r0, c1, r1]` within the camera rectangle,
+    // which is the part of the tilemap visible through the transform.
+    fn visible_tiles(&self, m: Matrix2d) -> [usize; 4] {
+        let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+        if det == 0.0 {
+            return [0; 4];
+        }
+        // Map the corners of normalized device coordinates back to the tilemap.
+        let corners = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
+        let local = corners.iter().map(|&[x, y]: &[f64; 2]| {
+            let (x, y) = (x - m[0][2], y - m[1][2]);
+            [
+                (m[1][1] * x - m[0][1] * y) / det,
+                (m[0][0] * y - m[1][0] * x) / det,
+            ]
+        });
+        let (mut min, mut max) = ([f64::MAX; 2], [f64::MIN; 2]);
+        for [x, y] in local {
+            min = [min[0].min(x), min[1].min(y)];
+            max = [max[0].max(x), max[1].max(y)];
+        }
+        let [w, h] = self.tile_size;
+        let range = |min: f64, max: f64, size: u32, len: usize| {
+            let size = size.max(1) as f64;
+            let start = (min / size).floor().max(0.0).min(len as f64);
+            let end = (max / size).ceil().max(0.0).min(len as f64);
+            (start as usize, end as usize)
+        };
+        let (c0, c1) = range(min[0], max[0], w, self.columns);
+        let (r0, r1) = range(min[1], max[1], h, self.rows);
+        [c0, r0, c1, r1]
+    }
+
+    pub(crate) fn vertices(&self) -> &VertexBuffer<TexturedVertex> {
+        &self.vertices
+    }
+
+    pub(crate) fn indices(&self, n: usize) -> IndexBufferSlice<'_, u32> {
+        self.indices.slice(0..n).unwrap()
+    }
+}
@@ -0,0 +1,96 @@
+use glium::backend::Facade;
+use glium::vertex::{BufferCreationError, Vertex, VertexBufferSlice};
+use glium::{Api, CapabilitiesSource, Version, VertexBuffer};
+
+/// Streams vertices through a buffer used as a ring.
+///
+/// Batches are written one after another into regions of the buffer,
+/// so a region is not overwritten while a draw call might still read it.
+/// The buffer is orphaned only when wrapping around to the start.
+/// Vertices are collected in a staging buffer that is reused between writes.
+pub(crate) struct VertexStream<T: Copy> {
+    buffer: VertexBuffer<T>,
+    // The vertices of the next write.
+    pub staging: Vec<T>,
+    // The start of the pending batch.
+    start: usize,
+    // The end of the pending batch.
+    end: usize,
+}
+
+impl<T: Vertex + Send + 'static> VertexStream<T> {
+    /// Creates a new stream with room for a number of vertices.
+    ///
+    /// Uses a persistent-mapped buffer when supported by the context.
+    pub fn new<F: Facade>(window: &F, size: usize) -> Result<Self, BufferCreationError> {
+        let buffer = if supports_persistent(window) {
+            match VertexBuffer::empty_persistent(window, size) {
+                Ok(buffer) => buffer,
+                Err(_) => VertexBuffer::empty_dynamic(window, size)?,
+            }
+        } else {
+            VertexBuffer::empty_dynamic(window, size)?
+        };
+        Ok(VertexStream {
+            buffer,
+            staging: Vec::with_capacity(size),
+            start: 0,
+            end: 0,
+        })
+    }
+
+    /// Returns the number of pending vertices.
+    pub fn pending(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the staged vertices fit after the pending batch.
+    pub fn has_room(&self) -> bool {
+        self.end + self.staging.len() <= self.buffer.len()
+    }
+
+    /// Starts over at the beginning of the buffer.
+    ///
+    /// Pending vertices must be drawn first.
+    pub fn wrap(&mut self) {
+        self.start = 0;
+        self.end = 0;
+        self.buffer.invalidate();
+    }
+
+    /// Appends the staged vertices to the pending batch.
+    pub fn write_staging(&mut self) {
+        let n = self.staging.len();
+        if n == 0 {
+            return;
+        }
+        if !self.has_room() {
+            self.wrap();
+        }
+        self.buffer
+            .slice(self.end..self.end + n)
+            .unwrap()
+            .write(&self.staging);
+        self.end += n;
+    }
+
+    /// Returns the pending batch.
+    pub fn pending_slice(&self) -> VertexBufferSlice<'_, T> {
+        self.buffer.slice(self.start..self.end).unwrap()
+    }
+
+    /// Marks the pending batch as drawn.
+    pub fn consume(&mut self) {
+        self.start = self.end;
+    }
+}
+
+// Returns `true` if persistent-mapped buffers can be created.
+//
+// Only checks desktop OpenGL, since glium panics instead of returning an error
+// when creating them with `EXT_buffer_storage` on OpenGL ES.
+fn supports_persistent<F: Facade>(window: &F) -> bool {
+    let context = window.get_context();
+    *CapabilitiesSource::get_version(&**context) >= Version(Api::Gl, 4, 4)
+        || CapabilitiesSource::get_extensions(&**context).gl_arb_buffer_storage
+}
@@ -27,6 +27,8 @@ pub struct GliumWindow<W = GlutinWindow> {
     pub context: Rc<Context>,
     /// Event loop state.
     pub events: Events,
+    // The number of samples of the frame.
+    samples: u8,
 }
 
 impl<W> Deref for GliumWindow<W> {
@@ -43,6 +45,7 @@ impl<W> Clone for GliumWindow<W> {
             window: self.window.clone(),
             context: self.context.clone(),
             events: self.events.clone(),
+            samples: self.samples,
         }
     }
 }
@@ -53,7 +56,9 @@ where
 {
     fn build_from_window_settings(settings: &WindowSettings) -> Result<GliumWindow<W>, Box<dyn Error>> {
         let window: W = settings.clone().build()?;
-        GliumWindow::new(&Rc::new(RefCell::new(window))).map_err(|err| err.into())
+        let mut window = GliumWindow::new(&Rc::new(RefCell::new(window)))?;
+        window.samples = settings.get_samples();
+        Ok(window)
     }
 }
 
@@ -71,9 +76,21 @@ where
             window: window.clone(),
             context: context,
             events: Events::new(EventSettings::new()).swap_buffers(false),
+            samples: 0,
         })
     }
 
+    /// Gets the number of samples of the frame, 0 when it is not multisampled.
+    ///
+    /// This is the number of samples of the window settings,
+    /// or 0 for a window created with `GliumWindow::new`.
+    ///
+    /// Pass it to `Glium2d::set_frame_samples`,
+    /// so `Glium2d::draw` does not add its own multisampled target.
+    pub fn get_samples(&self) -> u8 {
+        self.samples
+    }
+
     /// Returns new frame.
     pub fn draw(&self) -> Frame {
         Frame::new(
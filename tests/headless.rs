@@ -0,0 +1,155 @@
+#![cfg(all(feature = "headless", feature = "image"))]
+
+extern crate glium_graphics;
+extern crate graphics;
+
+use glium_graphics::{
+    BlendEquation, CreateTexture, Format, Glium2d, Headless, Sprite, SpriteBatch, Texture,
+    TextureSettings, Tilemap,
+};
+
+// Renders with OpenGL ES, which has no persistent-mapped vertex buffers in glium.
+#[test]
+fn gles() {
+    let mut context = Headless::egl_gles().unwrap();
+    let mut g2d = Glium2d::detect(&context).unwrap();
+    let image = g2d
+        .draw_to_image(&mut context, 20, 20, |c, g| {
+            use graphics::*;
+
+            clear([0.0, 0.0, 1.0, 1.0], g);
+            rectangle([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 10.0, 20.0], c.transform, g);
+        })
+        .unwrap();
+    assert_eq!(image.get_pixel(5, 10).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(15, 10).0, [0, 0, 255, 255]);
+}
+
+// Draws without clearing onto a texture with a red background.
+#[test]
+fn msaa_keeps_surface() {
+    let mut context = Headless::egl().unwrap();
+    let mut g2d = Glium2d::detect(&context).unwrap();
+    let texture = Texture::empty_with_size(&mut context, 20, 20, &TextureSettings::new()).unwrap();
+    g2d.draw_to_texture(&context, &texture, |_, g| {
+        graphics::clear([1.0, 0.0, 0.0, 1.0], g)
+    })
+    .unwrap();
+    g2d.set_samples(4);
+    g2d.draw_to_texture(&context, &texture, |c, g| {
+        graphics::rectangle([0.0, 0.0, 1.0, 1.0], [0.0, 0.0, 10.0, 20.0], c.transform, g)
+    })
+    .unwrap();
+    let image = texture.read_image();
+    assert_eq!(image.get_pixel(5, 10).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(15, 10).0, [255, 0, 0, 255]);
+}
+
+// Smooths the edge of a triangle when the frame starts with a clear.
+#[test]
+fn msaa_after_clear() {
+    let mut context = Headless::egl().unwrap();
+    let mut g2d = Glium2d::detect(&context).unwrap().samples(4);
+    let image = g2d
+        .draw_to_image(&mut context, 20, 20, |c, g| {
+            use graphics::*;
+
+            clear([0.0, 0.0, 0.0, 1.0], g);
+            polygon(
+                [1.0; 4],
+                &[[0.0, 0.0], [20.0, 0.0], [0.0, 20.0]],
+                c.transform,
+                g,
+            );
+        })
+        .unwrap();
+    let edge = (0..20).map(|x| image.get_pixel(x, 19 - x).0[0]);
+    assert!(edge.clone().any(|v| v > 0 && v < 255));
+    assert_eq!(image.get_pixel(2, 2).0, [255; 4]);
+    assert_eq!(image.get_pixel(18, 18).0, [0, 0, 0, 255]);
+}
+
+// Screens a half-transparent white over red, which keeps the red channel at full brightness.
+#[test]
+fn screen_half_transparent() {
+    let mut context = Headless::egl().unwrap();
+    let mut g2d = Glium2d::detect(&context).unwrap();
+    let image = g2d
+        .draw_to_image(&mut context, 20, 20, |c, g| {
+            use graphics::*;
+
+            clear([1.0, 0.0, 0.0, 1.0], g);
+            g.with_blend(BlendEquation::Screen, |g| {
+                rectangle([1.0, 1.0, 1.0, 0.5], [0.0, 0.0, 10.0, 20.0], c.transform, g);
+                rectangle(
+                    [1.0, 1.0, 1.0, 0.0],
+                    [10.0, 0.0, 10.0, 20.0],
+                    c.transform,
+                    g,
+                );
+            });
+        })
+        .unwrap();
+    let [r, g, b, a] = image.get_pixel(5, 10).0;
+    assert_eq!([r, a], [255, 255]);
+    assert!(g > 0 && g < 255 && g == b);
+    assert_eq!(image.get_pixel(15, 10).0, [255, 0, 0, 255]);
+    assert!(!g2d.get_premultiplied_alpha());
+}
+
+fn solid(context: &mut Headless, color: [u8; 4]) -> Texture {
+    let data = color.repeat(4);
+    CreateTexture::create(
+        context,
+        Format::Rgba8,
+        &data,
+        [2, 2],
+        &TextureSettings::new(),
+    )
+    .unwrap()
+}
+
+// Counts the times the bound texture changed between draw calls the same way for all paths.
+#[test]
+fn texture_switches() {
+    let mut context = Headless::egl().unwrap();
+    let mut g2d = Glium2d::detect(&context).unwrap().collect_stats(true);
+    let a = solid(&mut context, [255, 0, 0, 255]);
+    let b = solid(&mut context, [0, 0, 255, 255]);
+    let mut tilemap = Tilemap::new(&mut context, a.clone(), [2, 2], [1, 1], &[Some(0)]).unwrap();
+    let mut sprites = SpriteBatch::new();
+    sprites.push(
+        &b,
+        Sprite::new([0.0, 0.0, 2.0, 2.0], graphics::math::identity()),
+    );
+    sprites.push(
+        &a,
+        Sprite::new([0.0, 0.0, 2.0, 2.0], graphics::math::identity()),
+    );
+    g2d.draw_to_image(&mut context, 20, 20, |c, g| {
+        use graphics::*;
+
+        let quad = |g: &mut glium_graphics::GliumGraphics<_>, texture: &Texture| {
+            g.tri_list_uv_c(&c.draw_state, texture, |f| {
+                f(
+                    &[[0.0, 0.0], [0.5, 0.0], [0.0, 0.5]],
+                    &[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+                    &[[1.0; 4]; 3],
+                )
+            })
+        };
+        // Binds a, then b.
+        quad(g, &a);
+        quad(g, &b);
+        quad(g, &b);
+        // Keeps b bound, then binds a.
+        g.draw_sprites(&c.draw_state, &sprites);
+        // Keeps a bound.
+        g.draw_tilemap(&c.draw_state, &[1.0; 4], &mut tilemap, c.transform);
+        g.draw_tilemap(&c.draw_state, &[1.0; 4], &mut tilemap, c.transform);
+        // Binds b.
+        image(&b, c.transform, g);
+    })
+    .unwrap();
+    assert_eq!(g2d.get_stats().unwrap().texture_switches, 4);
+}